@@ -0,0 +1,35 @@
+//! Finds the network file embedded with the `embedded-network` feature.
+//!
+//! The file is given in the `RSCHESS_NETWORK` environment variable, relative to the crate root
+//! or absolute. Without the variable an empty file is embedded, so the crate still builds,
+//! but there is no network to use.
+//!
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=RSCHESS_NETWORK");
+    if env::var_os("CARGO_FEATURE_EMBEDDED_NETWORK").is_none() {
+        return;
+    }
+    let path = match env::var_os("RSCHESS_NETWORK") {
+        Some(path) => {
+            // Joining an absolute path keeps just the latter.
+            let path = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap()).join(path);
+            println!("cargo:rerun-if-changed={}", path.display());
+            path
+        }
+        None => {
+            println!("cargo:warning=RSCHESS_NETWORK isn't set, so no network is embedded");
+            let path = PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("no_network.bin");
+            fs::write(&path, []).unwrap();
+            path
+        }
+    };
+    println!(
+        "cargo:rustc-env=RSCHESS_EMBEDDED_NETWORK={}",
+        path.display()
+    );
+}
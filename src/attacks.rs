@@ -0,0 +1,153 @@
+//! Fields attacked by the pieces.
+//!
+//! The knight and king attacks are looked up in tables built at compile time. The sliding
+//! attacks are calculated on the fly, walking the board in the piece directions.
+//!
+
+use crate::{Bitboard, Color, Direction, Field, Piece, Position};
+use strum::IntoEnumIterator;
+
+/// Directions of the knight jumps.
+const KNIGHT_DIRECTIONS: [Direction; 8] = [
+    Direction::NNE,
+    Direction::NEE,
+    Direction::SEE,
+    Direction::SSE,
+    Direction::SSW,
+    Direction::SWW,
+    Direction::NWW,
+    Direction::NNW,
+];
+
+/// Directions of the king steps.
+const KING_DIRECTIONS: [Direction; 8] = [
+    Direction::N,
+    Direction::NE,
+    Direction::E,
+    Direction::SE,
+    Direction::S,
+    Direction::SW,
+    Direction::W,
+    Direction::NW,
+];
+
+/// Directions the bishop slides in.
+const BISHOP_DIRECTIONS: [Direction; 4] =
+    [Direction::NE, Direction::SE, Direction::SW, Direction::NW];
+
+/// Directions the rook slides in.
+const ROOK_DIRECTIONS: [Direction; 4] = [Direction::N, Direction::E, Direction::S, Direction::W];
+
+/// Fields attacked by a knight standing on each field.
+const KNIGHT_ATTACKS: [Bitboard; 64] = step_table(&KNIGHT_DIRECTIONS);
+
+/// Fields attacked by a king standing on each field.
+const KING_ATTACKS: [Bitboard; 64] = step_table(&KING_DIRECTIONS);
+
+/// Returns the fields attacked by the `pawns` of the `color`.
+pub fn pawn_attacks(pawns: Bitboard, color: Color) -> Bitboard {
+    match color {
+        Color::White => pawns.shift(Direction::NE) | pawns.shift(Direction::NW),
+        Color::Black => pawns.shift(Direction::SE) | pawns.shift(Direction::SW),
+    }
+}
+
+/// Returns the fields attacked by a knight standing on the `field`.
+pub fn knight_attacks(field: Field) -> Bitboard {
+    match field {
+        Field::INVALID => Bitboard::default(),
+        _ => KNIGHT_ATTACKS[field as usize],
+    }
+}
+
+/// Returns the fields attacked by a king standing on the `field`.
+pub fn king_attacks(field: Field) -> Bitboard {
+    match field {
+        Field::INVALID => Bitboard::default(),
+        _ => KING_ATTACKS[field as usize],
+    }
+}
+
+/// Returns the fields attacked by a bishop standing on the `field`, when the `occupied` fields block it.
+pub fn bishop_attacks(field: Field, occupied: Bitboard) -> Bitboard {
+    slides(field, occupied, &BISHOP_DIRECTIONS)
+}
+
+/// Returns the fields attacked by a rook standing on the `field`, when the `occupied` fields block it.
+pub fn rook_attacks(field: Field, occupied: Bitboard) -> Bitboard {
+    slides(field, occupied, &ROOK_DIRECTIONS)
+}
+
+/// Returns the fields attacked by a queen standing on the `field`, when the `occupied` fields block it.
+pub fn queen_attacks(field: Field, occupied: Bitboard) -> Bitboard {
+    bishop_attacks(field, occupied) | rook_attacks(field, occupied)
+}
+
+/// Returns the fields attacked by the `piece` of the `color` standing on the `field`.
+pub fn piece_attacks(piece: Piece, color: Color, field: Field, occupied: Bitboard) -> Bitboard {
+    match piece {
+        Piece::Pawn => pawn_attacks(Bitboard::from(field), color),
+        Piece::Knight => knight_attacks(field),
+        Piece::Bishop => bishop_attacks(field, occupied),
+        Piece::Rook => rook_attacks(field, occupied),
+        Piece::Queen => queen_attacks(field, occupied),
+        Piece::King => king_attacks(field),
+    }
+}
+
+/// Returns the fields attacked by all the pieces of the `piece` kind and the `color`.
+pub fn attacked_by_piece(position: &Position, color: Color, piece: Piece) -> Bitboard {
+    let pieces = position.pieces(color, piece);
+    match piece {
+        Piece::Pawn => pawn_attacks(pieces, color),
+        _ => {
+            let occupied = position.occupied();
+            pieces
+                .set_fields_iter()
+                .fold(Bitboard::default(), |acc, field| {
+                    acc | piece_attacks(piece, color, field, occupied)
+                })
+        }
+    }
+}
+
+/// Returns the fields attacked by any piece of the `color`.
+pub fn attacked_by(position: &Position, color: Color) -> Bitboard {
+    Piece::iter().fold(Bitboard::default(), |acc, piece| {
+        acc | attacked_by_piece(position, color, piece)
+    })
+}
+
+/// Collects the fields one step away in the `directions`, for each field of the board.
+const fn step_table(directions: &[Direction]) -> [Bitboard; 64] {
+    let mut table = [Bitboard::new(0); 64];
+    let mut index = 0;
+    while index < 64 {
+        let from = Bitboard::new(Bitboard::make_mask(index as u8));
+        let mut attacks = 0;
+        let mut direction = 0;
+        while direction < directions.len() {
+            attacks |= from.shift(directions[direction]).bits();
+            direction += 1;
+        }
+        table[index] = Bitboard::new(attacks);
+        index += 1;
+    }
+    table
+}
+
+/// Collects the fields in the `directions` from the `field`, up to and including the first occupied one.
+fn slides(field: Field, occupied: Bitboard, directions: &[Direction]) -> Bitboard {
+    let mut attacks = Bitboard::default();
+    for &direction in directions {
+        let mut current = field + direction;
+        while current != Field::INVALID {
+            attacks.set(current);
+            if occupied.is_set(current) {
+                break;
+            }
+            current = current + direction;
+        }
+    }
+    attacks
+}
@@ -0,0 +1,84 @@
+//! Error of the whole crate, wrapping the errors of the modules.
+//!
+
+use crate::nnue::NetworkError;
+use crate::options::OptionError;
+#[cfg(feature = "std")]
+use crate::uci::UciError;
+use crate::FenError;
+use core::fmt::Display;
+
+/// Any error returned by the crate, for passing them on with `?`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    Fen(FenError),
+    Network(NetworkError),
+    Option(OptionError),
+    #[cfg(feature = "std")]
+    Uci(UciError),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::Fen(error) => write!(f, "invalid FEN: {error}"),
+            Error::Network(error) => write!(f, "invalid network: {error}"),
+            Error::Option(error) => write!(f, "invalid option: {error}"),
+            #[cfg(feature = "std")]
+            Error::Uci(error) => write!(f, "UCI engine failed: {error}"),
+        }
+    }
+}
+
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Error::Fen(error) => Some(error),
+            Error::Network(error) => Some(error),
+            Error::Option(error) => Some(error),
+            #[cfg(feature = "std")]
+            Error::Uci(error) => Some(error),
+        }
+    }
+}
+
+impl From<FenError> for Error {
+    fn from(error: FenError) -> Self {
+        Error::Fen(error)
+    }
+}
+
+impl From<NetworkError> for Error {
+    fn from(error: NetworkError) -> Self {
+        Error::Network(error)
+    }
+}
+
+impl From<OptionError> for Error {
+    fn from(error: OptionError) -> Self {
+        Error::Option(error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<UciError> for Error {
+    fn from(error: UciError) -> Self {
+        Error::Uci(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Position;
+    use core::error::Error as _;
+
+    #[test]
+    fn wraps_module_errors() {
+        let parse = || -> Result<Position, Error> { Ok(Position::from_fen("8/8 w - -")?) };
+        let error = parse().unwrap_err();
+        assert!(matches!(error, Error::Fen(FenError::InvalidBoard { .. })));
+        assert!(error.source().is_some());
+    }
+}
@@ -0,0 +1,800 @@
+//! Static evaluation of a position.
+//!
+//! All the scores are in centipawns. The [evaluate] functions return the score from the
+//! point of view of the side to move, so a positive value is good for the player who
+//! is about to make a move.
+//!
+//! All the weights used by the evaluation are kept in [EvalParams], so they can be
+//! changed without touching the code.
+//!
+//! Every term has separate middlegame and endgame weights. The final score is an
+//! interpolation between the two, depending on the material left on the board (see [phase]).
+//!
+
+mod contempt;
+mod eval_cache;
+mod imbalance;
+mod king;
+mod mobility;
+mod mopup;
+mod outposts;
+mod passed;
+mod pawn_table;
+mod pawns;
+mod psqt;
+mod rooks;
+mod scale;
+mod space;
+mod threats;
+mod trace;
+pub mod verify;
+mod wdl;
+mod weights;
+
+pub use contempt::{draw_score, ContemptParams};
+pub use eval_cache::{CacheStats, EvalCache};
+pub use imbalance::{imbalance, ImbalanceParams};
+pub use king::{king_safety, KingSafetyParams, DANGER_SIZE};
+pub use mobility::{mobility, MobilityParams};
+pub use mopup::{mop_up, MopUpParams};
+pub use outposts::{outposts, outposts_score, OutpostParams};
+pub use passed::{front_span, passed_pawns, passed_pawns_score, PassedParams};
+pub use pawn_table::{PawnEntry, PawnTable};
+pub use pawns::{pawn_structure, PawnParams};
+pub use psqt::PieceSquareTables;
+pub use rooks::{rooks, RookParams};
+pub use scale::{fifty_move_scale, scale_factor, ScaleParams, SCALE_NORMAL};
+pub use space::{space, SpaceParams};
+pub use threats::{threats, ThreatParams};
+pub use trace::{trace, trace_with, EvalTrace, TermTrace};
+pub use wdl::{normalize, Wdl, WdlParams};
+#[cfg(feature = "std")]
+pub use wdl::{wdl, win_probability};
+pub use weights::WeightIndex;
+
+use crate::nnue::{Accumulator, Network, NetworkError};
+use crate::options::{OptionKind, Options};
+use crate::{Color, Field, Piece, Position};
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use core::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+use strum::IntoEnumIterator;
+
+/// Evaluation score in centipawns.
+pub type Score = i32;
+
+/// Game phase for the full starting material, see [phase].
+pub const MAX_PHASE: i32 = 24;
+
+/// How much each of the pieces contributes to the game phase.
+const PHASE_WEIGHTS: [i32; 6] = [0, 1, 1, 2, 4, 0];
+
+/// Parameters used by [evaluate], and by [Position] for keeping the incremental scores.
+static DEFAULT_PARAMS: EvalParams = EvalParams::DEFAULT;
+
+/// Pair of scores, one for the middlegame and one for the endgame.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct PhaseScore {
+    pub mg: Score,
+    pub eg: Score,
+}
+
+impl PhaseScore {
+    /// Creates new score from the middlegame and endgame values.
+    pub const fn new(mg: Score, eg: Score) -> Self {
+        Self { mg, eg }
+    }
+
+    /// Interpolates between the middlegame and endgame values for the given `phase`.
+    pub fn taper(self, phase: i32) -> Score {
+        let phase = phase.clamp(0, MAX_PHASE);
+        (self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+impl Add for PhaseScore {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.mg + rhs.mg, self.eg + rhs.eg)
+    }
+}
+
+impl Sub for PhaseScore {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.mg - rhs.mg, self.eg - rhs.eg)
+    }
+}
+
+impl Mul<Score> for PhaseScore {
+    type Output = Self;
+
+    fn mul(self, rhs: Score) -> Self::Output {
+        Self::new(self.mg * rhs, self.eg * rhs)
+    }
+}
+
+impl Div<Score> for PhaseScore {
+    type Output = Self;
+
+    fn div(self, rhs: Score) -> Self::Output {
+        Self::new(self.mg / rhs, self.eg / rhs)
+    }
+}
+
+impl Neg for PhaseScore {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.mg, -self.eg)
+    }
+}
+
+impl AddAssign for PhaseScore {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs
+    }
+}
+
+impl SubAssign for PhaseScore {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs
+    }
+}
+
+/// Values of the pieces used for counting the material.
+///
+/// The king is never exchanged, so its value is zero by default.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PieceValues {
+    values: [PhaseScore; 6],
+}
+
+impl PieceValues {
+    /// The default piece values.
+    pub const DEFAULT: Self = Self::new(
+        PhaseScore::new(100, 120),
+        PhaseScore::new(320, 300),
+        PhaseScore::new(330, 320),
+        PhaseScore::new(500, 530),
+        PhaseScore::new(900, 950),
+    );
+
+    /// Creates piece values from the arguments, with the king worth nothing.
+    pub const fn new(
+        pawn: PhaseScore,
+        knight: PhaseScore,
+        bishop: PhaseScore,
+        rook: PhaseScore,
+        queen: PhaseScore,
+    ) -> Self {
+        Self {
+            values: [pawn, knight, bishop, rook, queen, PhaseScore::new(0, 0)],
+        }
+    }
+
+    /// Returns value of the `piece`.
+    pub fn get(&self, piece: Piece) -> PhaseScore {
+        self.values[piece as usize]
+    }
+
+    /// Changes value of the `piece`.
+    pub fn set(&mut self, piece: Piece, value: PhaseScore) {
+        self.values[piece as usize] = value;
+    }
+}
+
+impl Default for PieceValues {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Margins of the lazy evaluation, see [Evaluator::evaluate_lazy].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LazyParams {
+    /// How far outside the window the cheap terms must be, to skip the expensive ones.
+    pub margin: Score,
+}
+
+impl LazyParams {
+    /// The default margins.
+    pub const DEFAULT: Self = Self { margin: 500 };
+}
+
+impl Default for LazyParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Choice between the hand-crafted evaluation and the network, see [Evaluator::set_mode].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum EvalMode {
+    /// Use the network when it's set, unless the material is very unbalanced.
+    #[default]
+    Auto,
+    /// Always use the hand-crafted evaluation.
+    Classical,
+    /// Always use the network when it's set.
+    Network,
+}
+
+/// Settings of switching between the hand-crafted evaluation and the network.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HybridParams {
+    /// Middlegame material difference above which the hand-crafted evaluation is used in the [EvalMode::Auto] mode.
+    ///
+    /// The networks see few such positions in the training, while the material alone is enough to judge them.
+    pub imbalance: Score,
+}
+
+impl HybridParams {
+    /// The default settings.
+    pub const DEFAULT: Self = Self { imbalance: 800 };
+}
+
+impl Default for HybridParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Weights of all the evaluation terms.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalParams {
+    pub piece_values: PieceValues,
+    pub psqt: PieceSquareTables,
+    pub pawns: PawnParams,
+    pub passed: PassedParams,
+    pub king_safety: KingSafetyParams,
+    pub mobility: MobilityParams,
+    pub imbalance: ImbalanceParams,
+    pub rooks: RookParams,
+    pub outposts: OutpostParams,
+    pub threats: ThreatParams,
+    pub space: SpaceParams,
+    pub scale: ScaleParams,
+    pub mop_up: MopUpParams,
+    pub lazy: LazyParams,
+    pub contempt: ContemptParams,
+    pub hybrid: HybridParams,
+    pub wdl: WdlParams,
+}
+
+impl EvalParams {
+    /// The default parameters.
+    pub const DEFAULT: Self = Self {
+        piece_values: PieceValues::DEFAULT,
+        psqt: PieceSquareTables::DEFAULT,
+        pawns: PawnParams::DEFAULT,
+        passed: PassedParams::DEFAULT,
+        king_safety: KingSafetyParams::DEFAULT,
+        mobility: MobilityParams::DEFAULT,
+        imbalance: ImbalanceParams::DEFAULT,
+        rooks: RookParams::DEFAULT,
+        outposts: OutpostParams::DEFAULT,
+        threats: ThreatParams::DEFAULT,
+        space: SpaceParams::DEFAULT,
+        scale: ScaleParams::DEFAULT,
+        mop_up: MopUpParams::DEFAULT,
+        lazy: LazyParams::DEFAULT,
+        contempt: ContemptParams::DEFAULT,
+        hybrid: HybridParams::DEFAULT,
+        wdl: WdlParams::DEFAULT,
+    };
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Calculates the game phase from the pieces left on the board.
+///
+/// It's [MAX_PHASE] for the starting position and goes down to zero when only kings and pawns are left.
+/// Promotions can't make it greater than [MAX_PHASE].
+pub fn phase(position: &Position) -> i32 {
+    let phase: i32 = Piece::iter()
+        .map(|piece| {
+            let count = position.pieces(Color::White, piece).count()
+                + position.pieces(Color::Black, piece).count();
+            count as i32 * PHASE_WEIGHTS[piece as usize]
+        })
+        .sum();
+    phase.min(MAX_PHASE)
+}
+
+/// Sums up values of all the pieces of the `color`.
+pub fn material(position: &Position, color: Color, values: &PieceValues) -> PhaseScore {
+    let mut score = PhaseScore::default();
+    for piece in Piece::iter() {
+        score += values.get(piece) * position.pieces(color, piece).count() as Score;
+    }
+    score
+}
+
+/// Sums up the piece-square bonuses of all the pieces of the `color`.
+pub fn piece_squares(position: &Position, color: Color, psqt: &PieceSquareTables) -> PhaseScore {
+    let mut score = PhaseScore::default();
+    for piece in Piece::iter() {
+        for field in position.pieces(color, piece).set_fields_iter() {
+            score += psqt.get(color, piece, field);
+        }
+    }
+    score
+}
+
+/// Returns the value and the piece-square bonus of a single piece for the default parameters.
+///
+/// The score is from the white point of view, so it's negative for the black pieces.
+/// This is what [Position] adds and subtracts when the pieces are put and removed.
+pub(crate) fn piece_score(color: Color, piece: Piece, field: Field) -> PhaseScore {
+    let score =
+        DEFAULT_PARAMS.piece_values.get(piece) + DEFAULT_PARAMS.psqt.get(color, piece, field);
+    match color {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
+
+/// Evaluates the position using the default parameters.
+///
+/// The material and piece-square terms are taken from the scores kept up to date by the [Position].
+/// Debug builds recalculate them from scratch to check that they are right.
+pub fn evaluate(position: &Position) -> Score {
+    let base = position.incremental_score();
+    debug_assert_eq!(
+        base,
+        base_terms(position, &DEFAULT_PARAMS),
+        "incremental score out of sync"
+    );
+    let mut pawns = PawnEntry::new(position, &DEFAULT_PARAMS);
+    finish(position, &DEFAULT_PARAMS, base, &mut pawns)
+}
+
+/// Evaluates the position using the given `params`.
+pub fn evaluate_with(position: &Position, params: &EvalParams) -> Score {
+    let mut pawns = PawnEntry::new(position, params);
+    finish(position, params, base_terms(position, params), &mut pawns)
+}
+
+/// Evaluation keeping the caches between the calls.
+///
+/// Use it for evaluating many positions with the same parameters, e.g. during the search.
+/// When a [Network] is set, it's used instead of the hand-crafted terms.
+/// With the `embedded-network` feature, the network embedded in the binary is set from the start.
+#[derive(Clone, Debug)]
+pub struct Evaluator {
+    params: EvalParams,
+    incremental: bool,
+    pawns: PawnTable,
+    cache: EvalCache,
+    network: Option<Arc<Network>>,
+    accumulator: Option<Accumulator>,
+    mode: EvalMode,
+}
+
+impl Evaluator {
+    /// Default number of the pawn table entries.
+    pub const PAWN_TABLE_SIZE: usize = 1 << 14;
+    /// Default number of the evaluation cache entries.
+    pub const EVAL_CACHE_SIZE: usize = 1 << 16;
+
+    /// Creates evaluator using the `params`.
+    pub fn new(params: EvalParams) -> Self {
+        Self {
+            incremental: params.piece_values == DEFAULT_PARAMS.piece_values
+                && params.psqt == DEFAULT_PARAMS.psqt,
+            params,
+            pawns: PawnTable::new(Self::PAWN_TABLE_SIZE),
+            cache: EvalCache::new(Self::EVAL_CACHE_SIZE),
+            network: embedded_network(),
+            accumulator: None,
+            mode: EvalMode::Auto,
+        }
+    }
+
+    /// Returns the parameters used by the evaluator.
+    pub fn params(&self) -> &EvalParams {
+        &self.params
+    }
+
+    /// Name of the option setting the contempt, see [ContemptParams].
+    pub const CONTEMPT_OPTION: &'static str = "Contempt";
+    /// Name of the option choosing the [EvalMode].
+    pub const MODE_OPTION: &'static str = "Eval Mode";
+    /// Name of the option with the path of the network file, empty for the embedded network if any. Needs `std`.
+    pub const NETWORK_OPTION: &'static str = "EvalFile";
+    /// Name of the button clearing the caches, see [Evaluator::clear].
+    pub const CLEAR_OPTION: &'static str = "Clear Hash";
+    /// Name of the check option adding [Wdl] to the `info` lines, read by their writer.
+    pub const SHOW_WDL_OPTION: &'static str = "UCI_ShowWDL";
+
+    /// Registers the evaluation options, see [Evaluator::configure].
+    pub fn register_options(options: &mut Options) {
+        let contempt = DEFAULT_PARAMS.contempt.contempt.mg as i64;
+        options.register(
+            Self::CONTEMPT_OPTION,
+            OptionKind::Spin {
+                default: contempt,
+                min: -100,
+                max: 100,
+            },
+        );
+        options.register(
+            Self::MODE_OPTION,
+            OptionKind::Combo {
+                default: "Auto".to_string(),
+                choices: ["Auto", "Classical", "Network"].map(String::from).to_vec(),
+            },
+        );
+        #[cfg(feature = "std")]
+        options.register(
+            Self::NETWORK_OPTION,
+            OptionKind::String {
+                default: String::new(),
+            },
+        );
+        options.register(Self::CLEAR_OPTION, OptionKind::Button);
+        options.register(Self::SHOW_WDL_OPTION, OptionKind::Check { default: false });
+    }
+
+    /// Applies the evaluation options changed since the last call, and clears the caches if the button was pressed.
+    ///
+    /// The contempt option sets the middlegame contempt, and half of it in the endgame.
+    /// Fails if the network file can't be loaded, keeping the previous network then.
+    pub fn configure(&mut self, options: &mut Options) -> Result<(), NetworkError> {
+        if options.take_changed(Self::CONTEMPT_OPTION) {
+            if let Some(contempt) = options.spin(Self::CONTEMPT_OPTION) {
+                let contempt = contempt as Score;
+                self.params.contempt.contempt = PhaseScore::new(contempt, contempt / 2);
+            }
+        }
+        if options.take_changed(Self::MODE_OPTION) {
+            let mode = match options.string(Self::MODE_OPTION) {
+                Some("Classical") => EvalMode::Classical,
+                Some("Network") => EvalMode::Network,
+                _ => EvalMode::Auto,
+            };
+            self.set_mode(mode);
+        }
+        #[cfg(feature = "std")]
+        if options.take_changed(Self::NETWORK_OPTION) {
+            match options.string(Self::NETWORK_OPTION) {
+                None | Some("") => self.set_network(embedded_network()),
+                Some(path) => self.set_network(Some(Arc::new(Network::load(path)?))),
+            }
+        }
+        if options.take_changed(Self::CLEAR_OPTION) {
+            self.clear();
+        }
+        Ok(())
+    }
+
+    /// Returns the network used instead of the hand-crafted evaluation, if any.
+    pub fn network(&self) -> Option<&Arc<Network>> {
+        self.network.as_ref()
+    }
+
+    /// Sets the network used instead of the hand-crafted evaluation, or goes back to the latter for `None`.
+    ///
+    /// Clears the evaluation cache, as the cached scores come from the previous evaluation.
+    pub fn set_network(&mut self, network: Option<Arc<Network>>) {
+        self.network = network;
+        self.accumulator = None;
+        self.cache.clear();
+    }
+
+    /// Returns the choice between the hand-crafted evaluation and the network.
+    pub fn mode(&self) -> EvalMode {
+        self.mode
+    }
+
+    /// Sets the choice between the hand-crafted evaluation and the network.
+    ///
+    /// Clears the evaluation cache, as the cached scores may come from the other evaluation.
+    pub fn set_mode(&mut self, mode: EvalMode) {
+        self.mode = mode;
+        self.cache.clear();
+    }
+
+    /// Tells if the position is evaluated by the network, depending on the [EvalMode].
+    pub fn uses_network(&self, position: &Position) -> bool {
+        let values = &self.params.piece_values;
+        match (self.mode, &self.network) {
+            (_, None) | (EvalMode::Classical, _) => false,
+            (EvalMode::Network, Some(_)) => true,
+            (EvalMode::Auto, Some(_)) => {
+                let imbalance = material(position, Color::White, values).mg
+                    - material(position, Color::Black, values).mg;
+                imbalance.abs() <= self.params.hybrid.imbalance
+            }
+        }
+    }
+
+    /// Evaluates the position, giving the same result as [evaluate_with] for the evaluator parameters,
+    /// or as [Network::evaluate] with the fifty-move rule scaling when the network is used (see [Evaluator::uses_network]).
+    ///
+    /// The scores are cached without the fifty-move rule scaling, as the key doesn't include the halfmove clock.
+    /// The network accumulator is updated from the previously evaluated position, so evaluating similar positions
+    /// one after another, like during the search, is much faster than evaluating unrelated ones.
+    pub fn evaluate(&mut self, position: &Position) -> Score {
+        let key = position.key();
+        let white = match self.cache.get(key) {
+            Some(white) => white,
+            None => {
+                let uses_network = self.uses_network(position);
+                let white = match self.network.as_deref().filter(|_| uses_network) {
+                    Some(network) => {
+                        let accumulator = match &mut self.accumulator {
+                            Some(accumulator) => {
+                                accumulator.update(network, position);
+                                accumulator
+                            }
+                            None => self.accumulator.insert(Accumulator::new(network, position)),
+                        };
+                        match position.side_to_move() {
+                            Color::White => accumulator.evaluate(network),
+                            Color::Black => -accumulator.evaluate(network),
+                        }
+                    }
+                    None => {
+                        let cheap = self.cheap_terms(position);
+                        let expensive = expensive_terms(position, &self.params);
+                        tapered(position, &self.params, cheap + expensive)
+                    }
+                };
+                self.cache.store(key, white);
+                white
+            }
+        };
+        for_side_to_move(position, &self.params, white)
+    }
+
+    /// Evaluates the position, skipping the expensive terms if the result can't fit in the window anyway.
+    ///
+    /// The `alpha` and `beta` bounds are from the point of view of the side to move.
+    /// When the cheap terms are further than [LazyParams::margin] outside the window,
+    /// the king attacks and threats aren't evaluated, and the returned score is just an estimate
+    /// which is still outside the window. Otherwise the result is the same as for [Evaluator::evaluate].
+    /// The network evaluation is never lazy.
+    pub fn evaluate_lazy(&mut self, position: &Position, alpha: Score, beta: Score) -> Score {
+        if self.uses_network(position) {
+            return self.evaluate(position);
+        }
+        let key = position.key();
+        if let Some(white) = self.cache.get(key) {
+            return for_side_to_move(position, &self.params, white);
+        }
+
+        let cheap = self.cheap_terms(position);
+        let estimate = for_side_to_move(
+            position,
+            &self.params,
+            tapered(position, &self.params, cheap),
+        );
+        let margin = self.params.lazy.margin;
+        if estimate - margin >= beta || estimate + margin <= alpha {
+            return estimate;
+        }
+
+        let white = tapered(
+            position,
+            &self.params,
+            cheap + expensive_terms(position, &self.params),
+        );
+        self.cache.store(key, white);
+        for_side_to_move(position, &self.params, white)
+    }
+
+    /// Returns the score of a drawn position for the side to move, when the engine plays the `engine` color.
+    pub fn draw_score(&self, position: &Position, engine: Color) -> Score {
+        draw_score(position, engine, &self.params.contempt)
+    }
+
+    /// Converts the raw `score` to the reported centipawns, see [normalize].
+    pub fn normalize(&self, score: Score, position: &Position) -> Score {
+        normalize(score, position, &self.params.wdl)
+    }
+
+    /// Returns the chances of the side to move with the raw `score`, see [wdl].
+    #[cfg(feature = "std")]
+    pub fn wdl(&self, score: Score, position: &Position) -> Wdl {
+        wdl(score, position, &self.params.wdl)
+    }
+
+    /// Calculates the terms which are fast to evaluate, using the caches.
+    fn cheap_terms(&mut self, position: &Position) -> PhaseScore {
+        let base = match self.incremental {
+            true => position.incremental_score(),
+            false => base_terms(position, &self.params),
+        };
+        let pawns = self.pawns.probe(position, &self.params);
+        cheap_terms(position, &self.params, base, pawns)
+    }
+
+    /// Returns the evaluation cache statistics.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
+    /// Removes everything from the caches.
+    ///
+    /// Do it before each new game, so the games of a match don't depend on each other.
+    pub fn clear(&mut self) {
+        self.pawns.clear();
+        self.cache.clear();
+    }
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new(EvalParams::default())
+    }
+}
+
+/// Returns the network embedded in the binary, if there is a valid one.
+#[cfg(feature = "embedded-network")]
+fn embedded_network() -> Option<Arc<Network>> {
+    Network::embedded().ok().map(Arc::new)
+}
+
+/// Returns no network, as none is embedded without the `embedded-network` feature.
+#[cfg(not(feature = "embedded-network"))]
+fn embedded_network() -> Option<Arc<Network>> {
+    None
+}
+
+/// Calculates the material and piece-square terms, from the white point of view.
+fn base_terms(position: &Position, params: &EvalParams) -> PhaseScore {
+    let material = material(position, Color::White, &params.piece_values)
+        - material(position, Color::Black, &params.piece_values);
+    let psqt = piece_squares(position, Color::White, &params.psqt)
+        - piece_squares(position, Color::Black, &params.psqt);
+    material + psqt
+}
+
+/// Adds the remaining terms to the `base` ones and returns the score for the side to move.
+fn finish(
+    position: &Position,
+    params: &EvalParams,
+    base: PhaseScore,
+    pawns: &mut PawnEntry,
+) -> Score {
+    let score = cheap_terms(position, params, base, pawns) + expensive_terms(position, params);
+    for_side_to_move(position, params, tapered(position, params, score))
+}
+
+/// Adds the terms which are fast to evaluate to the `base` ones, from the white point of view.
+///
+/// The pawn-only parts are taken from the `pawns` entry.
+fn cheap_terms(
+    position: &Position,
+    params: &EvalParams,
+    base: PhaseScore,
+    pawns: &mut PawnEntry,
+) -> PhaseScore {
+    base + cheap_side_terms(position, Color::White, params, pawns)
+        - cheap_side_terms(position, Color::Black, params, pawns)
+        + mop_up(position, &params.piece_values, &params.mop_up)
+}
+
+/// Sums up the fast terms calculated for each side separately, apart from the material and piece-squares.
+fn cheap_side_terms(
+    position: &Position,
+    color: Color,
+    params: &EvalParams,
+    pawns: &mut PawnEntry,
+) -> PhaseScore {
+    pawns.structure(color)
+        + passed::score_passed(position, color, pawns.passed(color), &params.passed)
+        + pawns.shelter(position, color, &params.king_safety)
+        + mobility(position, color, &params.mobility)
+        + imbalance(position, color, &params.imbalance)
+        + rooks(position, color, &params.rooks)
+        + outposts_score(position, color, &params.outposts)
+        + space(position, color, &params.space)
+}
+
+/// Calculates the terms which are slow to evaluate, from the white point of view.
+fn expensive_terms(position: &Position, params: &EvalParams) -> PhaseScore {
+    expensive_side_terms(position, Color::White, params)
+        - expensive_side_terms(position, Color::Black, params)
+}
+
+/// Sums up the slow terms calculated for each side separately: the king attacks and the threats.
+fn expensive_side_terms(position: &Position, color: Color, params: &EvalParams) -> PhaseScore {
+    let king_attacks = match king::king_field(position, color) {
+        None => PhaseScore::default(),
+        Some(king) => king::king_zone_attacks(position, color, king, &params.king_safety),
+    };
+    king_attacks + threats(position, color, &params.threats)
+}
+
+/// Scales the endgame part of the white `score` in drawish endgames and tapers it by the game phase.
+fn tapered(position: &Position, params: &EvalParams, mut score: PhaseScore) -> Score {
+    let strong = match score.eg >= 0 {
+        true => Color::White,
+        false => Color::Black,
+    };
+    score.eg = score.eg * scale_factor(position, strong, &params.scale) / SCALE_NORMAL;
+    score.taper(phase(position))
+}
+
+/// Applies the fifty-move rule scaling to the `white` score, and returns it for the side to move.
+fn for_side_to_move(position: &Position, params: &EvalParams, white: Score) -> Score {
+    let white = fifty_move_scale(white, position, &params.scale);
+    match position.side_to_move() {
+        Color::White => white,
+        Color::Black => -white,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_position_is_balanced() {
+        let start =
+            Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1").unwrap();
+        assert_eq!(evaluate(&start), 0);
+        assert_eq!(classical().evaluate(&start), 0);
+        assert_eq!(evaluate(&start.mirrored()), 0);
+    }
+
+    /// Creates an evaluator using only the hand-crafted evaluation, even with the embedded network.
+    fn classical() -> Evaluator {
+        let mut evaluator = Evaluator::default();
+        evaluator.set_mode(EvalMode::Classical);
+        evaluator
+    }
+
+    #[test]
+    fn lazy_evaluation() {
+        let position = Position::from_fen("4k3/pppp4/8/8/8/8/PPPP4/3QK3 w - - 0 1").unwrap();
+        let full = classical().evaluate(&position);
+        assert!(full > 500);
+        assert_eq!(
+            classical().evaluate_lazy(&position, -Score::MAX, Score::MAX),
+            full
+        );
+
+        let margin = LazyParams::DEFAULT.margin;
+        for (alpha, beta) in [(99, 100), (full + 2 * margin, full + 2 * margin + 1)] {
+            let mut evaluator = classical();
+            let lazy = evaluator.evaluate_lazy(&position, alpha, beta);
+            assert!(lazy >= beta || lazy <= alpha, "{lazy} in ({alpha}, {beta})");
+            assert!((lazy - full).abs() <= margin, "{lazy} vs {full}");
+            // The estimate isn't cached, so the full evaluation is done again.
+            assert_eq!(evaluator.evaluate(&position), full);
+            assert_eq!(evaluator.cache_stats().hits, 0);
+        }
+    }
+
+    #[cfg(feature = "embedded-network")]
+    #[test]
+    fn defaults_to_embedded_network() {
+        let embedded = Network::embedded().ok();
+        let mut evaluator = Evaluator::default();
+        assert_eq!(
+            evaluator.network().map(|network| &**network),
+            embedded.as_ref()
+        );
+        let mut options = Options::new();
+        Evaluator::register_options(&mut options);
+        options
+            .set_from_command("setoption name EvalFile value")
+            .unwrap();
+        evaluator.set_network(None);
+        evaluator.configure(&mut options).unwrap();
+        assert_eq!(
+            evaluator.network().map(|network| &**network),
+            embedded.as_ref()
+        );
+    }
+}
@@ -0,0 +1,84 @@
+//! Draw score biased by the contempt.
+//!
+
+use super::{phase, PhaseScore, Score};
+use crate::{Color, Position};
+
+/// Weights of the draw score.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ContemptParams {
+    /// How much worse than zero a draw is for the engine.
+    ///
+    /// Positive values make the engine avoid draws, which pays off against weaker opponents,
+    /// negative ones make it look for draws against stronger opponents.
+    pub contempt: PhaseScore,
+}
+
+impl ContemptParams {
+    /// The default weights.
+    pub const DEFAULT: Self = Self {
+        contempt: PhaseScore::new(20, 10),
+    };
+}
+
+impl Default for ContemptParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Returns the score of a drawn position for the side to move, when the engine plays the `engine` color.
+///
+/// The contempt is tapered by the game phase, so the draws can be valued differently
+/// in the middlegame and in the endgame.
+pub fn draw_score(position: &Position, engine: Color, params: &ContemptParams) -> Score {
+    let contempt = params.contempt.taper(phase(position));
+    match position.side_to_move() == engine {
+        true => -contempt,
+        false => contempt,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::Evaluator;
+    use crate::options::Options;
+    use alloc::format;
+
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1";
+
+    #[test]
+    fn draw_score_sign() {
+        let params = ContemptParams::DEFAULT;
+        let mut position = Position::from_fen(START).unwrap();
+        assert_eq!(draw_score(&position, Color::White, &params), -20);
+        assert_eq!(draw_score(&position, Color::Black, &params), 20);
+        position.set_side_to_move(Color::Black);
+        assert_eq!(draw_score(&position, Color::White, &params), 20);
+        assert_eq!(draw_score(&position, Color::Black, &params), -20);
+
+        // In the endgame the contempt is smaller.
+        let endgame = Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(draw_score(&endgame, Color::White, &params), -10);
+    }
+
+    #[test]
+    fn contempt_option() {
+        let mut options = Options::new();
+        Evaluator::register_options(&mut options);
+        options
+            .set_from_command(&format!(
+                "setoption name {} value 50",
+                Evaluator::CONTEMPT_OPTION
+            ))
+            .unwrap();
+        let mut evaluator = Evaluator::default();
+        evaluator.configure(&mut options).unwrap();
+        let start = Position::from_fen(START).unwrap();
+        assert_eq!(evaluator.draw_score(&start, Color::White), -50);
+        assert_eq!(evaluator.draw_score(&start, Color::Black), 50);
+        let endgame = Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(evaluator.draw_score(&endgame, Color::White), -25);
+    }
+}
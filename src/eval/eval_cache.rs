@@ -0,0 +1,114 @@
+//! Cache for the evaluation results.
+//!
+
+use super::Score;
+use alloc::{vec, vec::Vec};
+
+/// Numbers of the successful and failed cache lookups.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheStats {
+    /// Returns the part of the lookups which found the entry, from 0 to 1.
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            total => self.hits as f64 / total as f64,
+        }
+    }
+}
+
+/// Hash table with the evaluation scores, indexed by the [Position::key](crate::Position::key).
+///
+/// Each key has just one slot, so a newer score replaces the older one.
+#[derive(Clone, Debug)]
+pub struct EvalCache {
+    entries: Vec<Option<(u64, Score)>>,
+    stats: CacheStats,
+}
+
+impl EvalCache {
+    /// Creates a cache with at least `size` entries, rounded up to a power of two.
+    pub fn new(size: usize) -> Self {
+        Self {
+            entries: vec![None; size.max(1).next_power_of_two()],
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Returns the score stored for the `key`.
+    pub fn get(&mut self, key: u64) -> Option<Score> {
+        let found = match self.entries[self.index(key)] {
+            Some((stored, score)) if stored == key => Some(score),
+            _ => None,
+        };
+        match found {
+            Some(_) => self.stats.hits += 1,
+            None => self.stats.misses += 1,
+        }
+        found
+    }
+
+    /// Stores the `score` for the `key`.
+    pub fn store(&mut self, key: u64, score: Score) {
+        let index = self.index(key);
+        self.entries[index] = Some((key, score));
+    }
+
+    /// Returns the lookup statistics since the cache was created or cleared.
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Removes all the entries and resets the statistics.
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+        self.stats = CacheStats::default();
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key as usize) & (self.entries.len() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_and_get() {
+        let mut cache = EvalCache::new(16);
+        assert_eq!(cache.get(7), None);
+        cache.store(7, -25);
+        assert_eq!(cache.get(7), Some(-25));
+        cache.store(7, 30);
+        assert_eq!(cache.get(7), Some(30));
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 1 });
+        assert_eq!(cache.stats().hit_rate(), 2.0 / 3.0);
+    }
+
+    #[test]
+    fn key_collision() {
+        // Both keys go to the same slot, which keeps the full key to tell them apart.
+        let mut cache = EvalCache::new(16);
+        cache.store(3, 10);
+        assert_eq!(cache.get(3 + 16), None);
+        cache.store(3 + 16, 20);
+        assert_eq!(cache.get(3), None);
+        assert_eq!(cache.get(3 + 16), Some(20));
+    }
+
+    #[test]
+    fn clear() {
+        let mut cache = EvalCache::new(16);
+        cache.store(5, 10);
+        assert_eq!(cache.get(5), Some(10));
+        cache.clear();
+        assert_eq!(cache.stats(), CacheStats::default());
+        assert_eq!(cache.stats().hit_rate(), 0.0);
+        assert_eq!(cache.get(5), None);
+    }
+}
@@ -0,0 +1,109 @@
+//! Material imbalance evaluation.
+//!
+//! Corrections to the plain sum of the piece values, following the ideas of Larry Kaufman's
+//! "The Evaluation of Material Imbalances": some pieces work better in pairs, some are redundant,
+//! and the value of the knights, bishops and rooks depends on the number of the pawns left.
+//!
+
+use super::{PhaseScore, Score};
+use crate::{Color, Piece, Position};
+
+/// Number of own pawns at which the knights, bishops and rooks keep their plain value.
+const BASE_PAWNS: Score = 5;
+
+/// Weights of the material imbalance terms.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImbalanceParams {
+    /// Bonus for having at least two bishops.
+    pub bishop_pair: PhaseScore,
+    /// For having at least two knights.
+    pub knight_pair: PhaseScore,
+    /// For having at least two rooks.
+    pub rook_pair: PhaseScore,
+    /// For each knight, times the number of own pawns above five (or below, then negated).
+    pub knight_pawns: PhaseScore,
+    /// For each bishop, times the number of own pawns above five (or below, then negated).
+    pub bishop_pawns: PhaseScore,
+    /// For each rook, times the number of own pawns above five (or below, then negated).
+    pub rook_pawns: PhaseScore,
+}
+
+impl ImbalanceParams {
+    /// The default weights.
+    pub const DEFAULT: Self = Self {
+        bishop_pair: PhaseScore::new(30, 50),
+        knight_pair: PhaseScore::new(-8, -8),
+        rook_pair: PhaseScore::new(-16, -16),
+        knight_pawns: PhaseScore::new(6, 6),
+        bishop_pawns: PhaseScore::new(-2, -2),
+        rook_pawns: PhaseScore::new(-12, -12),
+    };
+}
+
+impl Default for ImbalanceParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Evaluates material imbalance of the pieces of the `color`.
+pub fn imbalance(position: &Position, color: Color, params: &ImbalanceParams) -> PhaseScore {
+    let count = |piece| position.pieces(color, piece).count() as Score;
+    let (knights, bishops, rooks) = (
+        count(Piece::Knight),
+        count(Piece::Bishop),
+        count(Piece::Rook),
+    );
+    let extra_pawns = count(Piece::Pawn) - BASE_PAWNS;
+
+    let mut score = params.knight_pawns * (knights * extra_pawns)
+        + params.bishop_pawns * (bishops * extra_pawns)
+        + params.rook_pawns * (rooks * extra_pawns);
+    if bishops >= 2 {
+        score += params.bishop_pair;
+    }
+    if knights >= 2 {
+        score += params.knight_pair;
+    }
+    if rooks >= 2 {
+        score += params.rook_pair;
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::verify::random_positions;
+
+    #[test]
+    fn bishop_pair() {
+        let params = ImbalanceParams::DEFAULT;
+        let imbalance = |fen| imbalance(&Position::from_fen(fen).unwrap(), Color::White, &params);
+        let pair = imbalance("4k3/8/8/8/8/8/PPPPP3/2B1KB2 w - - 0 1");
+        assert_eq!(pair, params.bishop_pair);
+        let single = imbalance("4k3/8/8/8/8/8/PPPPP3/2B1K3 w - - 0 1");
+        assert_eq!(single, PhaseScore::default());
+        // Without the pawns the bishops are worth a bit more.
+        assert_eq!(
+            imbalance("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1"),
+            params.bishop_pair + params.bishop_pawns * -10
+        );
+    }
+
+    #[test]
+    fn mirrored_material() {
+        let params = ImbalanceParams::DEFAULT;
+        for position in random_positions(6, 200) {
+            let mirrored = position.mirrored();
+            for color in [Color::White, Color::Black] {
+                assert_eq!(
+                    imbalance(&position, color, &params),
+                    imbalance(&mirrored, !color, &params),
+                    "{}",
+                    position.to_fen()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! King safety evaluation.
+//!
+
+use super::{PhaseScore, Score};
+use crate::attacks::{king_attacks, piece_attacks};
+use crate::{Bitboard, Color, Field, File, Piece, Position, Rank};
+use strum::IntoEnumIterator;
+
+/// Number of entries in the king danger table.
+pub const DANGER_SIZE: usize = 100;
+
+/// Weights of the king safety terms.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KingSafetyParams {
+    /// For each file around the king without our pawn right in front of the king.
+    pub shield_missing: PhaseScore,
+    /// For each file around the king where the shield pawn stands two ranks in front of the king,
+    /// instead of right in front of it.
+    pub shield_advanced: PhaseScore,
+    /// For each file around the king without our pawns.
+    pub semi_open_file: PhaseScore,
+    /// For each file around the king without any pawns.
+    pub open_file: PhaseScore,
+    /// Attack units for each attack of the piece on the king zone.
+    pub attack_units: [Score; 6],
+    /// Middlegame penalty for the number of the attack units on the king zone.
+    pub danger: [Score; DANGER_SIZE],
+}
+
+/// Builds the default king danger table, growing quadratically up to the limit.
+const fn default_danger() -> [Score; DANGER_SIZE] {
+    let mut table = [0; DANGER_SIZE];
+    let mut units = 0;
+    while units < DANGER_SIZE {
+        let value = (units * units / 7) as Score;
+        table[units] = if value > 500 { 500 } else { value };
+        units += 1;
+    }
+    table
+}
+
+impl KingSafetyParams {
+    /// The default weights.
+    pub const DEFAULT: Self = Self {
+        shield_missing: PhaseScore::new(-20, 0),
+        shield_advanced: PhaseScore::new(-10, 0),
+        semi_open_file: PhaseScore::new(-10, 0),
+        open_file: PhaseScore::new(-20, 0),
+        attack_units: [0, 2, 2, 3, 5, 0],
+        danger: default_danger(),
+    };
+}
+
+impl Default for KingSafetyParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Evaluates safety of the king of the `color`.
+///
+/// Combines the pawn shield in front of the king, the open files next to it
+/// and the enemy attacks on the fields around it.
+pub fn king_safety(position: &Position, color: Color, params: &KingSafetyParams) -> PhaseScore {
+    let Some(king) = king_field(position, color) else {
+        return PhaseScore::default();
+    };
+    shelter(position, color, king, params) + king_zone_attacks(position, color, king, params)
+}
+
+/// Returns the field of the king of the `color`.
+pub(super) fn king_field(position: &Position, color: Color) -> Option<Field> {
+    position.pieces(color, Piece::King).set_fields_iter().next()
+}
+
+/// Evaluates the pawns and open files on the king file and the adjacent ones.
+///
+/// Depends only on the pawns and the king field, so it can be kept in the pawn table.
+pub(super) fn shelter(
+    position: &Position,
+    color: Color,
+    king: Field,
+    params: &KingSafetyParams,
+) -> PhaseScore {
+    let pawns = position.pieces(color, Piece::Pawn);
+    let enemy_pawns = position.pieces(!color, Piece::Pawn);
+    let step: i8 = match color {
+        Color::White => 1,
+        Color::Black => -1,
+    };
+    let near = Bitboard::rank(Rank::from(king.rank() as i8 + step));
+    let far = Bitboard::rank(Rank::from(king.rank() as i8 + 2 * step));
+    let king_file = king.file() as i8;
+
+    let mut score = PhaseScore::default();
+    for file in (king_file - 1..=king_file + 1).map(File::from) {
+        if file == File::INVALID {
+            continue;
+        }
+        let file_mask = Bitboard::file(file);
+        let shield = pawns & file_mask;
+        if (shield & near).is_empty() {
+            score += match (shield & far).is_empty() {
+                true => params.shield_missing,
+                false => params.shield_advanced,
+            };
+        }
+        if shield.is_empty() {
+            score += match (enemy_pawns & file_mask).is_empty() {
+                true => params.open_file,
+                false => params.semi_open_file,
+            };
+        }
+    }
+    score
+}
+
+/// Evaluates the enemy attacks on the fields around the king.
+pub(super) fn king_zone_attacks(
+    position: &Position,
+    color: Color,
+    king: Field,
+    params: &KingSafetyParams,
+) -> PhaseScore {
+    let zone = king_attacks(king) | Bitboard::from(king);
+    let occupied = position.occupied();
+
+    let mut attackers = 0;
+    let mut units = 0;
+    for piece in Piece::iter() {
+        for field in position.pieces(!color, piece).set_fields_iter() {
+            let hits = (piece_attacks(piece, !color, field, occupied) & zone).count() as Score;
+            if hits > 0 && params.attack_units[piece as usize] > 0 {
+                attackers += 1;
+                units += hits * params.attack_units[piece as usize];
+            }
+        }
+    }
+
+    match attackers < 2 {
+        true => PhaseScore::default(),
+        false => {
+            let index = (units as usize).min(DANGER_SIZE - 1);
+            PhaseScore::new(-params.danger[index], 0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Evaluates the safety of the white king, checking the mirrored position gets the same score.
+    fn white_king(fen: &str) -> PhaseScore {
+        let params = KingSafetyParams::DEFAULT;
+        let position = Position::from_fen(fen).unwrap();
+        let score = king_safety(&position, Color::White, &params);
+        let mirrored = position.mirrored();
+        assert_eq!(
+            king_safety(&mirrored, Color::Black, &params),
+            score,
+            "{fen}"
+        );
+        score
+    }
+
+    #[test]
+    fn pawn_shield() {
+        let params = KingSafetyParams::DEFAULT;
+        assert_eq!(
+            white_king("4k3/8/8/8/8/8/5PPP/6K1 w - - 0 1"),
+            PhaseScore::default()
+        );
+        assert_eq!(
+            white_king("4k3/8/8/8/8/6P1/5P1P/6K1 w - - 0 1"),
+            params.shield_advanced
+        );
+        assert_eq!(
+            white_king("4k3/6p1/8/8/8/8/5P1P/6K1 w - - 0 1"),
+            params.shield_missing + params.semi_open_file
+        );
+        assert_eq!(
+            white_king("4k3/8/8/8/8/8/5P1P/6K1 w - - 0 1"),
+            params.shield_missing + params.open_file
+        );
+        // Only the files on the board are counted.
+        assert_eq!(
+            white_king("4k3/8/8/8/8/8/8/7K w - - 0 1"),
+            (params.shield_missing + params.open_file) * 2
+        );
+    }
+
+    #[test]
+    fn king_attacks() {
+        let params = KingSafetyParams::DEFAULT;
+        // The queen hits f2 and h2, the rook hits f2.
+        let units = 2 * params.attack_units[Piece::Queen as usize]
+            + params.attack_units[Piece::Rook as usize];
+        assert_eq!(
+            white_king("4kr2/8/8/8/7q/8/5PPP/6K1 w - - 0 1"),
+            PhaseScore::new(-params.danger[units as usize], 0)
+        );
+        assert!(params.danger[units as usize] > 0);
+        // A single attacker isn't counted.
+        assert_eq!(
+            white_king("4k3/8/8/8/7q/8/5PPP/6K1 w - - 0 1"),
+            PhaseScore::default()
+        );
+    }
+}
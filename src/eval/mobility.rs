@@ -0,0 +1,112 @@
+//! Mobility evaluation.
+//!
+
+use super::{PhaseScore, Score};
+use crate::attacks::{pawn_attacks, piece_attacks};
+use crate::{Color, Piece, Position};
+
+/// Weights of the mobility term, indexed by the number of fields the piece can safely go to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MobilityParams {
+    pub knight: [PhaseScore; 9],
+    pub bishop: [PhaseScore; 14],
+    pub rook: [PhaseScore; 15],
+    pub queen: [PhaseScore; 28],
+}
+
+/// Builds a table growing linearly with the mobility, zero for the `center` number of fields.
+const fn linear<const N: usize>(center: Score, mg: Score, eg: Score) -> [PhaseScore; N] {
+    let mut table = [PhaseScore::new(0, 0); N];
+    let mut count = 0;
+    while count < N {
+        let offset = count as Score - center;
+        table[count] = PhaseScore::new(offset * mg, offset * eg);
+        count += 1;
+    }
+    table
+}
+
+impl MobilityParams {
+    /// The default weights.
+    pub const DEFAULT: Self = Self {
+        knight: linear(4, 4, 4),
+        bishop: linear(7, 5, 5),
+        rook: linear(7, 2, 4),
+        queen: linear(14, 1, 2),
+    };
+
+    /// Returns the table for the `piece`, empty for the pawns and the king.
+    pub fn table(&self, piece: Piece) -> &[PhaseScore] {
+        match piece {
+            Piece::Knight => &self.knight,
+            Piece::Bishop => &self.bishop,
+            Piece::Rook => &self.rook,
+            Piece::Queen => &self.queen,
+            Piece::Pawn | Piece::King => &[],
+        }
+    }
+}
+
+impl Default for MobilityParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Evaluates mobility of the knights, bishops, rooks and queens of the `color`.
+///
+/// Fields occupied by our pieces, or attacked by the enemy pawns, are not counted.
+pub fn mobility(position: &Position, color: Color, params: &MobilityParams) -> PhaseScore {
+    let occupied = position.occupied();
+    let safe =
+        !(position.occupied_by(color) | pawn_attacks(position.pieces(!color, Piece::Pawn), !color));
+
+    let mut score = PhaseScore::default();
+    for piece in [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen] {
+        let table = params.table(piece);
+        for field in position.pieces(color, piece).set_fields_iter() {
+            let count = (piece_attacks(piece, color, field, occupied) & safe).count() as usize;
+            score += table[count.min(table.len() - 1)];
+        }
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Evaluates the mobility of the white, checking the mirrored position gets the same score.
+    fn white_mobility(fen: &str) -> PhaseScore {
+        let params = MobilityParams::DEFAULT;
+        let position = Position::from_fen(fen).unwrap();
+        let score = mobility(&position, Color::White, &params);
+        let mirrored = position.mirrored();
+        assert_eq!(mobility(&mirrored, Color::Black, &params), score, "{fen}");
+        score
+    }
+
+    #[test]
+    fn open_and_blocked_board() {
+        let params = MobilityParams::DEFAULT;
+        let open = white_mobility("7k/8/8/8/3R4/8/8/K7 w - - 0 1");
+        assert_eq!(open, params.rook[14]);
+        let blocked = white_mobility("7k/8/8/3P4/2PRP3/3P4/8/K7 w - - 0 1");
+        assert_eq!(blocked, params.rook[0]);
+        assert!(open.mg > blocked.mg && open.eg > blocked.eg);
+    }
+
+    #[test]
+    fn fields_attacked_by_pawns() {
+        let params = MobilityParams::DEFAULT;
+        // The knight can take on c6 and e6, but b5 and f5 are attacked by those pawns.
+        assert_eq!(
+            white_mobility("7k/8/2p1p3/8/3N4/8/8/K7 w - - 0 1"),
+            params.knight[6]
+        );
+        assert_eq!(
+            white_mobility("7k/8/8/8/3N4/8/8/K7 w - - 0 1"),
+            params.knight[8]
+        );
+    }
+}
@@ -0,0 +1,123 @@
+//! Mop-up evaluation for the won endgames.
+//!
+
+use super::{material, PhaseScore, PieceValues, Score};
+use crate::{Color, Field, Piece, Position};
+
+/// Weights of the mop-up term.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MopUpParams {
+    /// Endgame material advantage needed to start driving the enemy king.
+    pub threshold: Score,
+    /// For each step the enemy king is away from the center.
+    pub corner: Score,
+    /// For each step our king is closer to the enemy king.
+    pub proximity: Score,
+}
+
+impl MopUpParams {
+    /// The default weights.
+    pub const DEFAULT: Self = Self {
+        threshold: 400,
+        corner: 10,
+        proximity: 4,
+    };
+}
+
+impl Default for MopUpParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Returns number of king moves needed to reach the field from the nearest of the central fields,
+/// counted separately along the file and the rank.
+fn center_distance(field: Field) -> Score {
+    let file = field.file() as Score;
+    let rank = field.rank() as Score;
+    (3 - file).max(file - 4) + (3 - rank).max(rank - 4)
+}
+
+/// Returns the distance between the fields, counted separately along the files and the ranks.
+fn manhattan_distance(one: Field, two: Field) -> Score {
+    (one.file() as Score - two.file() as Score).abs()
+        + (one.rank() as Score - two.rank() as Score).abs()
+}
+
+/// Evaluates how well the winning side pushes the lone enemy king, from the white point of view.
+///
+/// Applies only when there are no pawns on the board, and one side is far ahead in material.
+/// Then the stronger side gets a bonus for driving the enemy king to the edge of the board,
+/// and for bringing its own king closer, which is needed to give the mate.
+pub fn mop_up(position: &Position, values: &PieceValues, params: &MopUpParams) -> PhaseScore {
+    let pawns =
+        position.pieces(Color::White, Piece::Pawn) | position.pieces(Color::Black, Piece::Pawn);
+    if !pawns.is_empty() {
+        return PhaseScore::default();
+    }
+
+    let advantage =
+        material(position, Color::White, values).eg - material(position, Color::Black, values).eg;
+    let strong = match advantage {
+        _ if advantage >= params.threshold => Color::White,
+        _ if -advantage >= params.threshold => Color::Black,
+        _ => return PhaseScore::default(),
+    };
+
+    let king = position
+        .pieces(strong, Piece::King)
+        .set_fields_iter()
+        .next();
+    let enemy_king = position
+        .pieces(!strong, Piece::King)
+        .set_fields_iter()
+        .next();
+    let (Some(king), Some(enemy_king)) = (king, enemy_king) else {
+        return PhaseScore::default();
+    };
+
+    let bonus = params.corner * center_distance(enemy_king)
+        + params.proximity * (14 - manhattan_distance(king, enemy_king));
+    match strong {
+        Color::White => PhaseScore::new(0, bonus),
+        Color::Black => PhaseScore::new(0, -bonus),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+
+    /// Evaluates the mop-up, checking the mirrored position gets the negated score.
+    fn mop_up_score(fen: &str) -> Score {
+        let (values, params) = (PieceValues::DEFAULT, MopUpParams::DEFAULT);
+        let position = Position::from_fen(fen).unwrap();
+        let score = mop_up(&position, &values, &params);
+        assert_eq!(
+            mop_up(&position.mirrored(), &values, &params),
+            -score,
+            "{fen}"
+        );
+        assert_eq!(score.mg, 0);
+        score.eg
+    }
+
+    #[test]
+    fn losing_king_pushed_to_corner() {
+        let scores = ["8/8/8/4k3", "8/8/5k2/8", "8/6k1/8/8", "7k/8/8/8"]
+            .map(|board| mop_up_score(&format!("{board}/8/2K5/8/Q7 w - - 0 1")));
+        assert!(scores[0] > 0);
+        assert!(
+            scores.windows(2).all(|pair| pair[0] < pair[1]),
+            "{scores:?}"
+        );
+    }
+
+    #[test]
+    fn only_won_endgames() {
+        assert_eq!(mop_up_score("7k/8/8/8/8/2K5/P7/Q7 w - - 0 1"), 0);
+        assert_eq!(mop_up_score("7k/8/8/8/8/2K5/8/Qq6 w - - 0 1"), 0);
+        assert_eq!(mop_up_score("7k/8/8/8/8/2K5/8/N7 w - - 0 1"), 0);
+    }
+}
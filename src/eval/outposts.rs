@@ -0,0 +1,123 @@
+//! Outposts evaluation.
+//!
+
+use super::{PhaseScore, Score};
+use crate::attacks::pawn_attacks;
+use crate::{Bitboard, Color, Piece, Position};
+
+/// Fields which can be outposts: ranks 4 to 6 for white, and 3 to 5 for black.
+const OUTPOST_FIELDS: [u64; 2] = [0x0000_FFFF_FF00_0000, 0x0000_00FF_FFFF_0000];
+
+/// Weights of the outpost terms.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OutpostParams {
+    /// For each knight on an outpost.
+    pub knight: PhaseScore,
+    /// For each bishop on an outpost.
+    pub bishop: PhaseScore,
+}
+
+impl OutpostParams {
+    /// The default weights.
+    pub const DEFAULT: Self = Self {
+        knight: PhaseScore::new(25, 15),
+        bishop: PhaseScore::new(12, 6),
+    };
+}
+
+impl Default for OutpostParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Returns the outposts of the `color`.
+///
+/// These are the fields on the ranks 4 to 6 as seen by us, defended by our pawn,
+/// which can't ever be attacked by the enemy pawns.
+pub fn outposts(position: &Position, color: Color) -> Bitboard {
+    let enemy_pawns = position.pieces(!color, Piece::Pawn);
+    let candidates = Bitboard::new(OUTPOST_FIELDS[color as usize])
+        & pawn_attacks(position.pieces(color, Piece::Pawn), color);
+
+    let mut outposts = Bitboard::default();
+    for field in candidates.set_fields_iter() {
+        let attackers_span =
+            Bitboard::adjacent_files(field.file()) & Bitboard::ranks_in_front(field.rank(), color);
+        if (enemy_pawns & attackers_span).is_empty() {
+            outposts.set(field);
+        }
+    }
+    outposts
+}
+
+/// Evaluates the knights and bishops of the `color` standing on the outposts.
+pub fn outposts_score(position: &Position, color: Color, params: &OutpostParams) -> PhaseScore {
+    let outposts = outposts(position, color);
+    let knights = (position.pieces(color, Piece::Knight) & outposts).count() as Score;
+    let bishops = (position.pieces(color, Piece::Bishop) & outposts).count() as Score;
+    params.knight * knights + params.bishop * bishops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Field;
+
+    /// Evaluates the outposts of the white, checking the mirrored position gets the same score.
+    fn white_outposts(fen: &str) -> PhaseScore {
+        let params = OutpostParams::DEFAULT;
+        let position = Position::from_fen(fen).unwrap();
+        let score = outposts_score(&position, Color::White, &params);
+        let mirrored = position.mirrored();
+        assert_eq!(
+            outposts_score(&mirrored, Color::Black, &params),
+            score,
+            "{fen}"
+        );
+        score
+    }
+
+    #[test]
+    fn supported_outpost() {
+        let params = OutpostParams::DEFAULT;
+        assert_eq!(
+            white_outposts("4k3/8/8/3N4/4P3/8/8/4K3 w - - 0 1"),
+            params.knight
+        );
+        assert_eq!(
+            white_outposts("4k3/8/8/3B4/4P3/8/8/4K3 w - - 0 1"),
+            params.bishop
+        );
+        // The fourth rank counts too, but not the third one.
+        assert_eq!(
+            white_outposts("4k3/8/8/8/3N4/4P3/8/4K3 w - - 0 1"),
+            params.knight
+        );
+        assert_eq!(
+            white_outposts("4k3/8/8/8/8/3N4/4P3/4K3 w - - 0 1"),
+            PhaseScore::default()
+        );
+        // Without the pawn support, it's not an outpost.
+        assert_eq!(
+            white_outposts("4k3/8/8/3N4/8/4P3/8/4K3 w - - 0 1"),
+            PhaseScore::default()
+        );
+    }
+
+    #[test]
+    fn field_a_pawn_can_attack() {
+        // The c7 pawn can go to c6 and attack the knight.
+        let position = Position::from_fen("4k3/2p5/8/3N4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        assert!(!outposts(&position, Color::White).is_set(Field::D5));
+        assert_eq!(
+            white_outposts("4k3/2p5/8/3N4/4P3/8/8/4K3 w - - 0 1"),
+            PhaseScore::default()
+        );
+        // A pawn already past the knight can't.
+        assert_eq!(
+            white_outposts("4k3/8/8/3N4/2p1P3/8/8/4K3 w - - 0 1"),
+            OutpostParams::DEFAULT.knight
+        );
+    }
+}
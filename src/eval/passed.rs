@@ -0,0 +1,179 @@
+//! Passed pawns evaluation.
+//!
+
+use super::{PhaseScore, Score};
+use crate::{Bitboard, Color, Direction, Field, Piece, Position};
+
+/// Weights of the passed pawn terms.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PassedParams {
+    /// Bonus for a passed pawn, by its rank as seen by its owner.
+    pub rank: [PhaseScore; 8],
+    /// For a passed pawn with any piece standing in front of it.
+    pub blocked: PhaseScore,
+    /// For each step the enemy king is further from the field in front of the pawn than our king.
+    pub king_proximity: PhaseScore,
+    /// For a rook of the same color standing behind the passed pawn.
+    pub rook_behind: PhaseScore,
+}
+
+impl PassedParams {
+    /// The default weights.
+    pub const DEFAULT: Self = Self {
+        rank: [
+            PhaseScore::new(0, 0),
+            PhaseScore::new(5, 10),
+            PhaseScore::new(5, 15),
+            PhaseScore::new(10, 25),
+            PhaseScore::new(25, 50),
+            PhaseScore::new(50, 90),
+            PhaseScore::new(90, 140),
+            PhaseScore::new(0, 0),
+        ],
+        blocked: PhaseScore::new(-10, -20),
+        king_proximity: PhaseScore::new(0, 5),
+        rook_behind: PhaseScore::new(10, 20),
+    };
+}
+
+impl Default for PassedParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Returns the fields which need to be free of the enemy pawns for a pawn on the `field` to be passed.
+///
+/// These are the fields in front of the pawn, on its file and both adjacent ones.
+pub fn front_span(field: Field, color: Color) -> Bitboard {
+    let files = Bitboard::file(field.file()) | Bitboard::adjacent_files(field.file());
+    files & Bitboard::ranks_in_front(field.rank(), color)
+}
+
+/// Returns the passed pawns of the `color`.
+pub fn passed_pawns(position: &Position, color: Color) -> Bitboard {
+    let pawns = position.pieces(color, Piece::Pawn);
+    let enemy_pawns = position.pieces(!color, Piece::Pawn);
+    let mut passed = Bitboard::default();
+    for field in pawns.set_fields_iter() {
+        if (front_span(field, color) & enemy_pawns).is_empty() {
+            passed.set(field);
+        }
+    }
+    passed
+}
+
+/// Evaluates the passed pawns of the `color`.
+pub fn passed_pawns_score(position: &Position, color: Color, params: &PassedParams) -> PhaseScore {
+    score_passed(position, color, passed_pawns(position, color), params)
+}
+
+/// Evaluates the `passed` pawns of the `color`, found earlier with [passed_pawns].
+pub(super) fn score_passed(
+    position: &Position,
+    color: Color,
+    passed: Bitboard,
+    params: &PassedParams,
+) -> PhaseScore {
+    let occupied = position.occupied();
+    let king = position.pieces(color, Piece::King).set_fields_iter().next();
+    let enemy_king = position
+        .pieces(!color, Piece::King)
+        .set_fields_iter()
+        .next();
+    let forward = match color {
+        Color::White => Direction::N,
+        Color::Black => Direction::S,
+    };
+
+    let mut score = PhaseScore::default();
+    for field in passed.set_fields_iter() {
+        score += params.rank[field.rank().relative(color) as usize];
+
+        let stop = field + forward;
+        if stop == Field::INVALID {
+            continue;
+        }
+        if occupied.is_set(stop) {
+            score += params.blocked;
+        }
+        if let (Some(king), Some(enemy_king)) = (king, enemy_king) {
+            let steps = enemy_king.distance(stop) as Score - king.distance(stop) as Score;
+            score += params.king_proximity * steps;
+        }
+        if has_rook_behind(position, field, color) {
+            score += params.rook_behind;
+        }
+    }
+    score
+}
+
+/// Checks if there is a rook of the `color` behind the pawn on the `field`, with nothing in between.
+fn has_rook_behind(position: &Position, field: Field, color: Color) -> bool {
+    let behind = Bitboard::file(field.file()) & Bitboard::ranks_in_front(field.rank(), !color);
+    let rooks = position.pieces(color, Piece::Rook) & behind;
+    let nearest = match color {
+        Color::White => rooks.set_fields_iter().last(),
+        Color::Black => rooks.set_fields_iter().next(),
+    };
+    match nearest {
+        None => false,
+        Some(rook) => {
+            let between = behind & Bitboard::ranks_in_front(rook.rank(), color);
+            (position.occupied() & between).is_empty()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The default weights, without the king proximity depending on where the kings are.
+    const PARAMS: PassedParams = PassedParams {
+        king_proximity: PhaseScore::new(0, 0),
+        ..PassedParams::DEFAULT
+    };
+
+    /// Evaluates the passed pawns of the white, checking the mirrored position gets the same score.
+    fn white_score(fen: &str) -> PhaseScore {
+        let position = Position::from_fen(fen).unwrap();
+        let score = passed_pawns_score(&position, Color::White, &PARAMS);
+        let mirrored = position.mirrored();
+        assert_eq!(
+            passed_pawns_score(&mirrored, Color::Black, &PARAMS),
+            score,
+            "{fen}"
+        );
+        score
+    }
+
+    #[test]
+    fn free_passed_pawn() {
+        assert_eq!(white_score("k7/8/8/4P3/8/8/8/K7 w - - 0 1"), PARAMS.rank[4]);
+        assert_eq!(white_score("k7/8/4P3/8/8/8/8/K7 w - - 0 1"), PARAMS.rank[5]);
+        // An enemy pawn already behind doesn't stop it.
+        assert_eq!(
+            white_score("k7/8/8/4P3/3p4/8/8/K7 w - - 0 1"),
+            PARAMS.rank[4]
+        );
+    }
+
+    #[test]
+    fn blocked_passed_pawn() {
+        let score = white_score("k7/8/4n3/4P3/8/8/8/K7 w - - 0 1");
+        assert_eq!(score, PARAMS.rank[4] + PARAMS.blocked);
+        assert!(score.mg < PARAMS.rank[4].mg);
+    }
+
+    #[test]
+    fn enemy_pawn_on_adjacent_file() {
+        let position = Position::from_fen("k7/3p4/8/4P3/8/8/8/K7 w - - 0 1").unwrap();
+        assert!(passed_pawns(&position, Color::White).is_empty());
+        assert!(passed_pawns(&position, Color::Black).is_empty());
+        assert_eq!(
+            white_score("k7/3p4/8/4P3/8/8/8/K7 w - - 0 1"),
+            PhaseScore::default()
+        );
+    }
+}
@@ -0,0 +1,161 @@
+//! Cache for the pawn structure evaluation.
+//!
+
+use super::king::{king_field, shelter};
+use super::{passed_pawns, pawn_structure, EvalParams, KingSafetyParams, PhaseScore};
+use crate::{Bitboard, Color, Field, Position};
+use alloc::{vec, vec::Vec};
+
+/// Evaluation of the pawns of a position, with the results which depend on the pawns only.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PawnEntry {
+    key: u64,
+    structure: [PhaseScore; 2],
+    passed: [Bitboard; 2],
+    shelter: [(Field, PhaseScore); 2],
+}
+
+impl PawnEntry {
+    /// Evaluates the pawns of the `position`.
+    pub fn new(position: &Position, params: &EvalParams) -> Self {
+        Self {
+            key: position.pawn_key(),
+            structure: [
+                pawn_structure(position, Color::White, &params.pawns),
+                pawn_structure(position, Color::Black, &params.pawns),
+            ],
+            passed: [
+                passed_pawns(position, Color::White),
+                passed_pawns(position, Color::Black),
+            ],
+            shelter: [(Field::INVALID, PhaseScore::default()); 2],
+        }
+    }
+
+    /// Returns the pawns key of the evaluated position.
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
+    /// Returns the pawn structure score of the `color`.
+    pub fn structure(&self, color: Color) -> PhaseScore {
+        self.structure[color as usize]
+    }
+
+    /// Returns the passed pawns of the `color`.
+    pub fn passed(&self, color: Color) -> Bitboard {
+        self.passed[color as usize]
+    }
+
+    /// Returns the pawn shelter score of the king of the `color`.
+    ///
+    /// It's calculated again only if the king has moved since the last call.
+    pub fn shelter(
+        &mut self,
+        position: &Position,
+        color: Color,
+        params: &KingSafetyParams,
+    ) -> PhaseScore {
+        let Some(king) = king_field(position, color) else {
+            return PhaseScore::default();
+        };
+        let (cached_king, score) = &mut self.shelter[color as usize];
+        if *cached_king != king {
+            *cached_king = king;
+            *score = shelter(position, color, king, params);
+        }
+        *score
+    }
+}
+
+/// Hash table with the pawn evaluations, indexed by the [Position::pawn_key].
+///
+/// The pawn structure changes rarely during the search, so most of the evaluations find it here.
+#[derive(Clone, Debug)]
+pub struct PawnTable {
+    entries: Vec<Option<PawnEntry>>,
+}
+
+impl PawnTable {
+    /// Creates a table with at least `size` entries, rounded up to a power of two.
+    pub fn new(size: usize) -> Self {
+        Self {
+            entries: vec![None; size.max(1).next_power_of_two()],
+        }
+    }
+
+    /// Returns the entry for the pawns of the `position`, evaluating them when they aren't in the table.
+    pub fn probe(&mut self, position: &Position, params: &EvalParams) -> &mut PawnEntry {
+        let key = position.pawn_key();
+        let index = (key as usize) & (self.entries.len() - 1);
+        let slot = &mut self.entries[index];
+        match slot {
+            Some(entry) if entry.key == key => {}
+            _ => *slot = Some(PawnEntry::new(position, params)),
+        }
+        slot.as_mut().unwrap()
+    }
+
+    /// Removes all the entries, needed e.g. when the evaluation parameters change.
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Probes the `position`, and makes the entry differ from a fresh one by caching the white shelter.
+    fn probe_cached(table: &mut PawnTable, position: &Position, params: &EvalParams) -> PawnEntry {
+        let entry = table.probe(position, params);
+        entry.shelter(position, Color::White, &params.king_safety);
+        let cached = *entry;
+        assert_ne!(cached, PawnEntry::new(position, params));
+        cached
+    }
+
+    #[test]
+    fn probe_by_pawn_key() {
+        let params = EvalParams::DEFAULT;
+        let position = Position::from_fen("4k3/pppp4/8/8/8/8/PPPP4/4K3 w - - 0 1").unwrap();
+        let same_pawns = Position::from_fen("4k3/pppp4/8/8/8/8/PPPP4/1N2K3 b - - 0 1").unwrap();
+        let other_pawns = Position::from_fen("4k3/ppp5/8/8/8/8/PPPP4/4K3 w - - 0 1").unwrap();
+        assert_eq!(position.pawn_key(), same_pawns.pawn_key());
+        assert_ne!(position.key(), same_pawns.key());
+
+        let mut table = PawnTable::new(16);
+        let cached = probe_cached(&mut table, &position, &params);
+        assert_eq!(*table.probe(&same_pawns, &params), cached);
+
+        let entry = *table.probe(&other_pawns, &params);
+        assert_eq!(entry, PawnEntry::new(&other_pawns, &params));
+        assert_eq!(entry.key(), other_pawns.pawn_key());
+        assert_eq!(
+            entry.structure(Color::Black),
+            pawn_structure(&other_pawns, Color::Black, &params.pawns)
+        );
+
+        // With a single slot, the other pawns replace the cached entry.
+        let mut table = PawnTable::new(1);
+        probe_cached(&mut table, &position, &params);
+        table.probe(&other_pawns, &params);
+        assert_eq!(
+            *table.probe(&position, &params),
+            PawnEntry::new(&position, &params)
+        );
+    }
+
+    #[test]
+    fn clear() {
+        let params = EvalParams::DEFAULT;
+        let position = Position::from_fen("4k3/pppp4/8/8/8/8/PPPP4/4K3 w - - 0 1").unwrap();
+        let mut table = PawnTable::new(16);
+        probe_cached(&mut table, &position, &params);
+        table.clear();
+        assert_eq!(
+            *table.probe(&position, &params),
+            PawnEntry::new(&position, &params)
+        );
+    }
+}
@@ -0,0 +1,125 @@
+//! Pawn structure evaluation.
+//!
+
+use super::PhaseScore;
+use crate::attacks::pawn_attacks;
+use crate::{Bitboard, Color, Direction, Field, Piece, Position};
+
+/// Weights of the pawn structure terms.
+///
+/// Penalties are negative, so all the weights can be simply added up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PawnParams {
+    /// For each pawn with another pawn of the same color in front of it, on the same file.
+    pub doubled: PhaseScore,
+    /// For each pawn without pawns of the same color on the adjacent files.
+    pub isolated: PhaseScore,
+    /// For each pawn left behind by its neighbours, which can't advance safely either.
+    pub backward: PhaseScore,
+    /// For each pawn defended by another pawn, or standing next to one.
+    pub connected: PhaseScore,
+}
+
+impl PawnParams {
+    /// The default weights.
+    pub const DEFAULT: Self = Self {
+        doubled: PhaseScore::new(-10, -20),
+        isolated: PhaseScore::new(-10, -15),
+        backward: PhaseScore::new(-8, -10),
+        connected: PhaseScore::new(10, 8),
+    };
+}
+
+impl Default for PawnParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Evaluates the structure of the pawns of the `color`.
+pub fn pawn_structure(position: &Position, color: Color, params: &PawnParams) -> PhaseScore {
+    let pawns = position.pieces(color, Piece::Pawn);
+    let enemy_attacks = pawn_attacks(position.pieces(!color, Piece::Pawn), !color);
+    let defended = pawn_attacks(pawns, color);
+    let forward = match color {
+        Color::White => Direction::N,
+        Color::Black => Direction::S,
+    };
+
+    let mut score = PhaseScore::default();
+    for field in pawns.set_fields_iter() {
+        let (file, rank) = (field.file(), field.rank());
+        let neighbours = pawns & Bitboard::adjacent_files(file);
+        let front = Bitboard::ranks_in_front(rank, color);
+
+        if !(pawns & Bitboard::file(file) & front).is_empty() {
+            score += params.doubled;
+        }
+        if neighbours.is_empty() {
+            score += params.isolated;
+        } else if (neighbours & !front).is_empty() {
+            let stop = field + forward;
+            if stop != Field::INVALID && enemy_attacks.is_set(stop) {
+                score += params.backward;
+            }
+        }
+        if defended.is_set(field) || !(neighbours & Bitboard::rank(rank)).is_empty() {
+            score += params.connected;
+        }
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Evaluates the pawns of both colors, checking the mirrored position gets the same scores.
+    fn scores(fen: &str) -> (PhaseScore, PhaseScore) {
+        let params = PawnParams::DEFAULT;
+        let position = Position::from_fen(fen).unwrap();
+        let scores = (
+            pawn_structure(&position, Color::White, &params),
+            pawn_structure(&position, Color::Black, &params),
+        );
+        let mirrored = position.mirrored();
+        assert_eq!(
+            (
+                pawn_structure(&mirrored, Color::Black, &params),
+                pawn_structure(&mirrored, Color::White, &params),
+            ),
+            scores,
+            "{fen}"
+        );
+        scores
+    }
+
+    #[test]
+    fn doubled_pawns() {
+        let params = PawnParams::DEFAULT;
+        let (white, black) = scores("4k3/8/8/8/8/3P4/3P4/4K3 w - - 0 1");
+        assert_eq!(white, params.doubled + params.isolated * 2);
+        assert_eq!(black, PhaseScore::default());
+        assert!(white.mg < 0 && white.eg < 0);
+    }
+
+    #[test]
+    fn isolated_pawns() {
+        let params = PawnParams::DEFAULT;
+        let (white, black) = scores("4k3/pp6/8/8/8/8/P1P5/4K3 w - - 0 1");
+        assert_eq!(white, params.isolated * 2);
+        assert_eq!(black, params.connected * 2);
+    }
+
+    #[test]
+    fn backward_pawns() {
+        let params = PawnParams::DEFAULT;
+        // The d2 pawn is behind the e4 one, and can't go to d3 attacked by the c4 pawn.
+        let (white, black) = scores("4k3/8/8/8/2p1P3/8/3P4/4K3 w - - 0 1");
+        assert_eq!(white, params.backward);
+        assert_eq!(black, params.isolated);
+        // With the stop field safe, the pawn isn't backward.
+        let (white, _) = scores("4k3/8/8/8/p3P3/8/3P4/4K3 w - - 0 1");
+        assert_eq!(white, PhaseScore::default());
+    }
+}
@@ -0,0 +1,117 @@
+//! Rook placement evaluation.
+//!
+
+use super::{PhaseScore, Score};
+use crate::attacks::rook_attacks;
+use crate::{Bitboard, Color, Piece, Position, Rank};
+
+/// Weights of the rook placement terms.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RookParams {
+    /// For each rook on a file without any pawns.
+    pub open_file: PhaseScore,
+    /// For each rook on a file with enemy pawns only.
+    pub semi_open_file: PhaseScore,
+    /// For each rook on the seventh rank, when there are enemy pawns there, or the enemy king is behind it.
+    pub seventh_rank: PhaseScore,
+    /// For each pair of rooks defending each other.
+    pub connected: PhaseScore,
+}
+
+impl RookParams {
+    /// The default weights.
+    pub const DEFAULT: Self = Self {
+        open_file: PhaseScore::new(25, 10),
+        semi_open_file: PhaseScore::new(12, 6),
+        seventh_rank: PhaseScore::new(10, 25),
+        connected: PhaseScore::new(10, 5),
+    };
+}
+
+impl Default for RookParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Evaluates placement of the rooks of the `color`.
+pub fn rooks(position: &Position, color: Color, params: &RookParams) -> PhaseScore {
+    let rooks = position.pieces(color, Piece::Rook);
+    let pawns = position.pieces(color, Piece::Pawn);
+    let enemy_pawns = position.pieces(!color, Piece::Pawn);
+    let seventh = Bitboard::rank(Rank::Rank7.relative(color));
+    let eighth = Bitboard::rank(Rank::Rank8.relative(color));
+    let seventh_matters = !(enemy_pawns & seventh).is_empty()
+        || !(position.pieces(!color, Piece::King) & eighth).is_empty();
+
+    let mut score = PhaseScore::default();
+    for field in rooks.set_fields_iter() {
+        let file = Bitboard::file(field.file());
+        if (pawns & file).is_empty() {
+            score += match (enemy_pawns & file).is_empty() {
+                true => params.open_file,
+                false => params.semi_open_file,
+            };
+        }
+        if seventh_matters && seventh.is_set(field) {
+            score += params.seventh_rank;
+        }
+    }
+
+    // Each pair is counted from both sides.
+    let defended: u32 = rooks
+        .set_fields_iter()
+        .map(|field| (rook_attacks(field, position.occupied()) & rooks).count())
+        .sum();
+    score + params.connected * (defended / 2) as Score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Evaluates the rooks of the white, checking the mirrored position gets the same score.
+    fn white_rooks(fen: &str) -> PhaseScore {
+        let params = RookParams::DEFAULT;
+        let position = Position::from_fen(fen).unwrap();
+        let score = rooks(&position, Color::White, &params);
+        let mirrored = position.mirrored();
+        assert_eq!(rooks(&mirrored, Color::Black, &params), score, "{fen}");
+        score
+    }
+
+    #[test]
+    fn open_files() {
+        let params = RookParams::DEFAULT;
+        assert_eq!(
+            white_rooks("4k3/8/8/8/8/8/8/R3K3 w - - 0 1"),
+            params.open_file
+        );
+        assert_eq!(
+            white_rooks("4k3/8/p7/8/8/8/8/R3K3 w - - 0 1"),
+            params.semi_open_file
+        );
+        assert_eq!(
+            white_rooks("4k3/8/p7/8/8/8/P7/R3K3 w - - 0 1"),
+            PhaseScore::default()
+        );
+    }
+
+    #[test]
+    fn seventh_rank() {
+        let params = RookParams::DEFAULT;
+        assert_eq!(
+            white_rooks("4k3/R7/8/8/8/8/8/4K3 w - - 0 1"),
+            params.open_file + params.seventh_rank
+        );
+        assert_eq!(
+            white_rooks("8/R3p3/4k3/8/8/8/8/4K3 w - - 0 1"),
+            params.open_file + params.seventh_rank
+        );
+        // Without the enemy king or pawns to attack, the seventh rank doesn't matter.
+        assert_eq!(
+            white_rooks("8/R7/4k3/8/8/8/8/4K3 w - - 0 1"),
+            params.open_file
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Scaling of the evaluation in drawish endgames.
+//!
+
+use super::Score;
+use crate::{Bitboard, Color, Piece, Position};
+
+/// Scale factor which keeps the endgame score as it is.
+pub const SCALE_NORMAL: Score = 64;
+
+/// Weights of the endgame scaling.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScaleParams {
+    /// Scale factor, out of 64, when the only pieces are the bishops on opposite colors.
+    pub opposite_bishops: Score,
+    /// Scale factor, out of 64, for opposite colored bishops, if there are other pieces as well.
+    pub opposite_bishops_with_pieces: Score,
+    /// Scale factor, out of 64, for a rook ending where the stronger side has at most one pawn more.
+    pub rook_ending: Score,
+    /// Number of half-moves without captures and pawn moves, at which the score would drop to zero.
+    pub fifty_move_decay: Score,
+}
+
+impl ScaleParams {
+    /// The default weights.
+    pub const DEFAULT: Self = Self {
+        opposite_bishops: 22,
+        opposite_bishops_with_pieces: 48,
+        rook_ending: 48,
+        fifty_move_decay: 200,
+    };
+}
+
+impl Default for ScaleParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Calculates how much of the endgame score the `strong` side can hope to convert into a win.
+///
+/// Returns [SCALE_NORMAL] for the positions which aren't drawish.
+pub fn scale_factor(position: &Position, strong: Color, params: &ScaleParams) -> Score {
+    let count = |color, piece| position.pieces(color, piece).count();
+    let others = |color| {
+        count(color, Piece::Knight) + count(color, Piece::Rook) + count(color, Piece::Queen)
+    };
+
+    if count(Color::White, Piece::Bishop) == 1 && count(Color::Black, Piece::Bishop) == 1 {
+        let bishops = position.pieces(Color::White, Piece::Bishop)
+            | position.pieces(Color::Black, Piece::Bishop);
+        let light = (bishops & Bitboard::new(Bitboard::LIGHT_FIELDS)).count();
+        if light == 1 {
+            return match others(Color::White) + others(Color::Black) {
+                0 => params.opposite_bishops,
+                _ => params.opposite_bishops_with_pieces,
+            };
+        }
+    }
+
+    let rook_only = |color| {
+        count(color, Piece::Rook) == 1
+            && count(color, Piece::Knight)
+                + count(color, Piece::Bishop)
+                + count(color, Piece::Queen)
+                == 0
+    };
+    if rook_only(Color::White) && rook_only(Color::Black) {
+        let pawns = count(strong, Piece::Pawn) as Score - count(!strong, Piece::Pawn) as Score;
+        if pawns <= 1 {
+            return params.rook_ending;
+        }
+    }
+    SCALE_NORMAL
+}
+
+/// Moves the `score` towards zero as the position approaches the fifty-move rule draw.
+pub fn fifty_move_scale(score: Score, position: &Position, params: &ScaleParams) -> Score {
+    let decay = params.fifty_move_decay.max(1);
+    // Clamping before the cast, as the clock may not fit the score.
+    let clock = position.halfmove_clock().min(decay as u32) as Score;
+    (score as i64 * (decay - clock) as i64 / decay as i64) as Score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fifty_move_scale_decays_to_zero() {
+        let params = ScaleParams::DEFAULT;
+        let mut position = Position::from_fen("4k3/8/8/8/8/8/8/4KQ2 w - - 0 1").unwrap();
+        assert_eq!(fifty_move_scale(900, &position, &params), 900);
+        position.set_halfmove_clock(100);
+        assert_eq!(fifty_move_scale(900, &position, &params), 450);
+        assert_eq!(fifty_move_scale(-900, &position, &params), -450);
+        position.set_halfmove_clock(params.fifty_move_decay as u32);
+        assert_eq!(fifty_move_scale(900, &position, &params), 0);
+        position.set_halfmove_clock(u32::MAX);
+        assert_eq!(fifty_move_scale(900, &position, &params), 0);
+        assert_eq!(fifty_move_scale(Score::MAX, &position, &params), 0);
+        assert_eq!(crate::eval::evaluate(&position), 0);
+    }
+}
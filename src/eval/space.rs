@@ -0,0 +1,99 @@
+//! Space evaluation.
+//!
+
+use super::{PhaseScore, Score};
+use crate::attacks::pawn_attacks;
+use crate::{Bitboard, Color, Direction, Piece, Position};
+
+/// Central files, from C to F, on the ranks 2 to 4 of each side.
+const SPACE_FIELDS: [u64; 2] = [0x0000_0000_3C3C_3C00, 0x003C_3C3C_0000_0000];
+
+/// Divisor of the space bonus, which is multiplied by both the number of fields and pieces.
+const SPACE_SCALE: Score = 16;
+
+/// Weights of the space term.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpaceParams {
+    /// For each safe field, times the number of our pieces, divided by 16.
+    pub per_field: PhaseScore,
+}
+
+impl SpaceParams {
+    /// The default weights.
+    pub const DEFAULT: Self = Self {
+        per_field: PhaseScore::new(3, 0),
+    };
+}
+
+impl Default for SpaceParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Evaluates the space controlled by the `color` in the center of the board.
+///
+/// Counts the central fields on our side of the board, which aren't occupied by our pawns,
+/// nor attacked by the enemy pawns. The fields up to three steps behind our pawns are counted twice.
+/// The more pieces are on the board, the more the space matters, so the score grows with them.
+pub fn space(position: &Position, color: Color, params: &SpaceParams) -> PhaseScore {
+    let pawns = position.pieces(color, Piece::Pawn);
+    let safe = Bitboard::new(SPACE_FIELDS[color as usize])
+        & !pawns
+        & !pawn_attacks(position.pieces(!color, Piece::Pawn), !color);
+
+    let backward = match color {
+        Color::White => Direction::S,
+        Color::Black => Direction::N,
+    };
+    let mut behind = pawns.shift(backward);
+    behind |= behind.shift(backward);
+    behind |= behind.shift(backward);
+
+    let fields = (safe.count() + (safe & behind).count()) as Score;
+    let pieces = (position.occupied_by(color) & !pawns).count() as Score;
+    params.per_field * (fields * pieces) / SPACE_SCALE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::phase;
+
+    /// Evaluates the space of both colors, checking the mirrored position gets the same scores.
+    fn scores(fen: &str) -> (PhaseScore, PhaseScore) {
+        let params = SpaceParams::DEFAULT;
+        let position = Position::from_fen(fen).unwrap();
+        let scores = (
+            space(&position, Color::White, &params),
+            space(&position, Color::Black, &params),
+        );
+        let mirrored = position.mirrored();
+        assert_eq!(
+            (
+                space(&mirrored, Color::Black, &params),
+                space(&mirrored, Color::White, &params),
+            ),
+            scores,
+            "{fen}"
+        );
+        scores
+    }
+
+    #[test]
+    fn no_space_in_endgame() {
+        let fen = "4k3/2p2p2/8/3p4/3P4/8/2P2P2/4K3 w - - 0 1";
+        let (white, black) = scores(fen);
+        let phase = phase(&Position::from_fen(fen).unwrap());
+        assert_eq!((white.taper(phase), black.taper(phase)), (0, 0));
+        assert_eq!((white.eg, black.eg), (0, 0));
+    }
+
+    #[test]
+    fn closed_center() {
+        let (white, black) =
+            scores("r1bqkbnr/pp3ppp/2n1p3/2ppP3/3P4/2P2N2/PP3PPP/RNBQKB1R w - - 0 1");
+        assert!(white.mg > 0);
+        assert!(white.mg > black.mg);
+    }
+}
@@ -0,0 +1,140 @@
+//! Threats evaluation.
+//!
+
+use super::{PhaseScore, Score};
+use crate::attacks::{attacked_by, attacked_by_piece};
+use crate::{Color, Piece, Position};
+use strum::IntoEnumIterator;
+
+/// Weights of the threat terms.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThreatParams {
+    /// For each enemy piece attacked by our knight or bishop, by the attacked piece.
+    pub by_minor: [PhaseScore; 6],
+    /// For each enemy piece attacked by our rook, by the attacked piece.
+    pub by_rook: [PhaseScore; 6],
+    /// For each undefended enemy piece we attack, when it's our move.
+    pub hanging: PhaseScore,
+}
+
+impl ThreatParams {
+    /// The default weights.
+    pub const DEFAULT: Self = Self {
+        by_minor: [
+            PhaseScore::new(5, 15),
+            PhaseScore::new(20, 20),
+            PhaseScore::new(20, 20),
+            PhaseScore::new(40, 30),
+            PhaseScore::new(40, 40),
+            PhaseScore::new(0, 0),
+        ],
+        by_rook: [
+            PhaseScore::new(5, 15),
+            PhaseScore::new(15, 20),
+            PhaseScore::new(15, 20),
+            PhaseScore::new(10, 15),
+            PhaseScore::new(40, 40),
+            PhaseScore::new(0, 0),
+        ],
+        hanging: PhaseScore::new(30, 20),
+    };
+}
+
+impl Default for ThreatParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Evaluates threats made by the pieces of the `color` to the enemy pieces.
+///
+/// The minors and rooks get bonuses for attacking enemy pieces which are worth more than them,
+/// or aren't defended at all. Undefended enemy pieces are also counted as hanging,
+/// if we're about to move and can take them.
+pub fn threats(position: &Position, color: Color, params: &ThreatParams) -> PhaseScore {
+    let defended = attacked_by(position, !color);
+    let by_minor = attacked_by_piece(position, color, Piece::Knight)
+        | attacked_by_piece(position, color, Piece::Bishop);
+    let by_rook = attacked_by_piece(position, color, Piece::Rook);
+
+    let mut score = PhaseScore::default();
+    for victim in Piece::iter().filter(|&piece| piece != Piece::King) {
+        let pieces = position.pieces(!color, victim);
+        let undefended = pieces & !defended;
+        let minor_targets = match victim {
+            Piece::Rook | Piece::Queen => pieces,
+            _ => undefended,
+        };
+        let rook_targets = match victim {
+            Piece::Queen => pieces,
+            _ => undefended,
+        };
+        score += params.by_minor[victim as usize] * (by_minor & minor_targets).count() as Score;
+        score += params.by_rook[victim as usize] * (by_rook & rook_targets).count() as Score;
+    }
+
+    if position.side_to_move() == color {
+        let enemies = position.occupied_by(!color) & !position.pieces(!color, Piece::King);
+        let hanging = enemies & !defended & attacked_by(position, color);
+        score += params.hanging * hanging.count() as Score;
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Evaluates the threats of the white, checking the mirrored position gets the same score.
+    fn white_threats(fen: &str) -> PhaseScore {
+        let params = ThreatParams::DEFAULT;
+        let position = Position::from_fen(fen).unwrap();
+        let score = threats(&position, Color::White, &params);
+        let mirrored = position.mirrored();
+        assert_eq!(threats(&mirrored, Color::Black, &params), score, "{fen}");
+        score
+    }
+
+    #[test]
+    fn hanging_piece() {
+        let params = ThreatParams::DEFAULT;
+        let by_rook = params.by_rook[Piece::Knight as usize];
+        assert_eq!(
+            white_threats("n3k3/8/8/8/8/8/8/R3K3 w - - 0 1"),
+            by_rook + params.hanging
+        );
+        // It's only hanging when we can take it.
+        assert_eq!(white_threats("n3k3/8/8/8/8/8/8/R3K3 b - - 0 1"), by_rook);
+        // A defended knight isn't a target for the rook.
+        assert_eq!(
+            white_threats("n3k3/1b6/8/8/8/8/8/R3K3 w - - 0 1"),
+            PhaseScore::default()
+        );
+    }
+
+    #[test]
+    fn minor_attacking_rook() {
+        let params = ThreatParams::DEFAULT;
+        assert_eq!(
+            white_threats("4k3/1p6/2r5/8/3N4/8/8/4K3 b - - 0 1"),
+            params.by_minor[Piece::Rook as usize]
+        );
+    }
+
+    #[test]
+    fn pawn_attacking_minor() {
+        let params = ThreatParams::DEFAULT;
+        assert_eq!(
+            white_threats("4k3/8/8/3n4/4P3/8/8/4K3 w - - 0 1"),
+            params.hanging
+        );
+        assert_eq!(
+            white_threats("4k3/8/8/3n4/4P3/8/8/4K3 b - - 0 1"),
+            PhaseScore::default()
+        );
+        assert_eq!(
+            white_threats("4k3/8/2p5/3n4/4P3/8/8/4K3 w - - 0 1"),
+            PhaseScore::default()
+        );
+    }
+}
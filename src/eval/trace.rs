@@ -0,0 +1,204 @@
+//! Breakdown of the evaluation into the terms, for debugging.
+//!
+
+use super::{
+    evaluate_with, imbalance, king_safety, material, mobility, mop_up, outposts_score,
+    passed_pawns_score, pawn_structure, phase, piece_squares, rooks, scale_factor, space, threats,
+    EvalParams, PhaseScore, Score, DEFAULT_PARAMS, MAX_PHASE, SCALE_NORMAL,
+};
+use crate::{Color, Position};
+use alloc::{vec, vec::Vec};
+use core::fmt::Display;
+
+/// Scores of a single evaluation term for both sides.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TermTrace {
+    pub name: &'static str,
+    pub white: PhaseScore,
+    pub black: PhaseScore,
+}
+
+impl TermTrace {
+    /// Returns the score of the term from the white point of view.
+    pub fn total(&self) -> PhaseScore {
+        self.white - self.black
+    }
+}
+
+/// Evaluation split into the terms, as returned by [trace].
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalTrace {
+    /// Scores of the terms, before the scaling and the tapering.
+    pub terms: Vec<TermTrace>,
+    /// The game phase, see [phase].
+    pub phase: i32,
+    /// The endgame scale factor for the stronger side, out of [SCALE_NORMAL].
+    pub scale: Score,
+    /// The final score for the side to move, the same as returned by [evaluate_with].
+    pub score: Score,
+    /// The color which moves next.
+    pub side_to_move: Color,
+}
+
+impl EvalTrace {
+    /// Returns the sum of all the terms from the white point of view.
+    pub fn total(&self) -> PhaseScore {
+        self.terms
+            .iter()
+            .fold(PhaseScore::default(), |acc, term| acc + term.total())
+    }
+}
+
+/// Evaluates the position using the default parameters, keeping the score of each term.
+pub fn trace(position: &Position) -> EvalTrace {
+    trace_with(position, &DEFAULT_PARAMS)
+}
+
+/// Evaluates the position using the given `params`, keeping the score of each term.
+pub fn trace_with(position: &Position, params: &EvalParams) -> EvalTrace {
+    let side = |name, term: &dyn Fn(Color) -> PhaseScore| TermTrace {
+        name,
+        white: term(Color::White),
+        black: term(Color::Black),
+    };
+    let mut terms = vec![
+        side("Material", &|color| {
+            material(position, color, &params.piece_values)
+        }),
+        side("PSQT", &|color| {
+            piece_squares(position, color, &params.psqt)
+        }),
+        side("Pawns", &|color| {
+            pawn_structure(position, color, &params.pawns)
+        }),
+        side("Passed", &|color| {
+            passed_pawns_score(position, color, &params.passed)
+        }),
+        side("King safety", &|color| {
+            king_safety(position, color, &params.king_safety)
+        }),
+        side("Mobility", &|color| {
+            mobility(position, color, &params.mobility)
+        }),
+        side("Imbalance", &|color| {
+            imbalance(position, color, &params.imbalance)
+        }),
+        side("Rooks", &|color| rooks(position, color, &params.rooks)),
+        side("Outposts", &|color| {
+            outposts_score(position, color, &params.outposts)
+        }),
+        side("Threats", &|color| {
+            threats(position, color, &params.threats)
+        }),
+        side("Space", &|color| space(position, color, &params.space)),
+    ];
+    let mop_up = mop_up(position, &params.piece_values, &params.mop_up);
+    terms.push(match mop_up.eg >= 0 {
+        true => TermTrace {
+            name: "Mop-up",
+            white: mop_up,
+            black: PhaseScore::default(),
+        },
+        false => TermTrace {
+            name: "Mop-up",
+            white: PhaseScore::default(),
+            black: -mop_up,
+        },
+    });
+
+    let mut trace = EvalTrace {
+        terms,
+        phase: phase(position),
+        scale: SCALE_NORMAL,
+        score: evaluate_with(position, params),
+        side_to_move: position.side_to_move(),
+    };
+    let strong = match trace.total().eg >= 0 {
+        true => Color::White,
+        false => Color::Black,
+    };
+    trace.scale = scale_factor(position, strong, &params.scale);
+    trace
+}
+
+impl Display for EvalTrace {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let line = "-------------+-------------+-------------+-------------";
+        writeln!(
+            f,
+            "{:>12} | {:^11} | {:^11} | {:^11}",
+            "Term", "White", "Black", "Total"
+        )?;
+        writeln!(
+            f,
+            "{:>12} | {:>5} {:>5} | {:>5} {:>5} | {:>5} {:>5}",
+            "", "MG", "EG", "MG", "EG", "MG", "EG"
+        )?;
+        writeln!(f, "{line}")?;
+        for term in &self.terms {
+            let total = term.total();
+            writeln!(
+                f,
+                "{:>12} | {:>5} {:>5} | {:>5} {:>5} | {:>5} {:>5}",
+                term.name,
+                term.white.mg,
+                term.white.eg,
+                term.black.mg,
+                term.black.eg,
+                total.mg,
+                total.eg
+            )?;
+        }
+        writeln!(f, "{line}")?;
+        let total = self.total();
+        writeln!(
+            f,
+            "{:>12} | {:>11} | {:>11} | {:>5} {:>5}",
+            "Total", "", "", total.mg, total.eg
+        )?;
+        writeln!(f)?;
+        writeln!(f, "Phase: {}/{MAX_PHASE}", self.phase)?;
+        writeln!(f, "Endgame scale: {}/{SCALE_NORMAL}", self.scale)?;
+        write!(f, "Score: {} for {:?}", self.score, self.side_to_move)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::verify::random_positions;
+    use crate::eval::{evaluate, fifty_move_scale};
+
+    /// Adds up the traced terms the same way as the evaluation does.
+    fn traced_score(position: &Position) -> Score {
+        let trace = trace(position);
+        let mut total = trace.total();
+        total.eg = total.eg * trace.scale / SCALE_NORMAL;
+        let white = fifty_move_scale(total.taper(trace.phase), position, &DEFAULT_PARAMS.scale);
+        match trace.side_to_move {
+            Color::White => white,
+            Color::Black => -white,
+        }
+    }
+
+    #[test]
+    fn total_matches_evaluation() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+            "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR b - - 4 1",
+            "8/8/4k3/8/2P5/8/5K2/8 w - - 30 1",
+            "4k3/8/8/8/8/8/8/4KQ2 b - - 0 1",
+            "8/5k2/8/3b4/8/2B5/P4K2/8 w - - 0 1",
+        ];
+        let positions = fens.map(|fen| Position::from_fen(fen).unwrap());
+        for position in positions.into_iter().chain(random_positions(7, 100)) {
+            assert_eq!(
+                traced_score(&position),
+                evaluate(&position),
+                "{}",
+                position.to_fen()
+            );
+            assert_eq!(trace(&position).score, evaluate(&position));
+        }
+    }
+}
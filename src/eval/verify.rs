@@ -0,0 +1,190 @@
+//! Consistency checks of the evaluation, for catching the bugs.
+//!
+
+use super::{
+    base_terms, evaluate_with, EvalMode, EvalParams, Evaluator, PhaseScore, Score, DEFAULT_PARAMS,
+};
+use crate::{zobrist, Color, Field, Piece, Position};
+use alloc::vec::Vec;
+use core::fmt::Display;
+use strum::IntoEnumIterator;
+
+/// Broken invariant of the evaluation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Inconsistency {
+    /// The color-flipped position gets a different score.
+    Asymmetric { score: Score, mirrored: Score },
+    /// The material and piece-square score kept by the position differs from the one calculated from scratch.
+    IncrementalScore {
+        incremental: PhaseScore,
+        full: PhaseScore,
+    },
+    /// The Zobrist keys kept by the position differ from the ones calculated from scratch.
+    Keys {
+        incremental: (u64, u64),
+        full: (u64, u64),
+    },
+    /// The evaluator with the caches gives a different score than the evaluation from scratch.
+    Cached { cached: Score, full: Score },
+}
+
+impl Display for Inconsistency {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Inconsistency::Asymmetric { score, mirrored } => {
+                write!(f, "score {score} differs from mirrored score {mirrored}")
+            }
+            Inconsistency::IncrementalScore { incremental, full } => write!(
+                f,
+                "incremental score {incremental:?} differs from full score {full:?}"
+            ),
+            Inconsistency::Keys { incremental, full } => write!(
+                f,
+                "incremental keys {incremental:x?} differ from full keys {full:x?}"
+            ),
+            Inconsistency::Cached { cached, full } => {
+                write!(f, "cached score {cached} differs from full score {full}")
+            }
+        }
+    }
+}
+
+/// Checks that the position and its color-flipped mirror get the same score with the `params`.
+pub fn check_symmetry(position: &Position, params: &EvalParams) -> Result<(), Inconsistency> {
+    let score = evaluate_with(position, params);
+    let mirrored = evaluate_with(&position.mirrored(), params);
+    match score == mirrored {
+        true => Ok(()),
+        false => Err(Inconsistency::Asymmetric { score, mirrored }),
+    }
+}
+
+/// Checks that the scores and the keys kept up to date by the position match the ones calculated from scratch.
+pub fn check_incremental(position: &Position) -> Result<(), Inconsistency> {
+    let incremental = position.incremental_score();
+    let full = base_terms(position, &DEFAULT_PARAMS);
+    if incremental != full {
+        return Err(Inconsistency::IncrementalScore { incremental, full });
+    }
+
+    let mut key = 0;
+    let mut pawn_key = 0;
+    for color in Color::iter() {
+        for piece in Piece::iter() {
+            for field in position.pieces(color, piece).set_fields_iter() {
+                key ^= zobrist::piece_key(color, piece, field);
+                if piece == Piece::Pawn {
+                    pawn_key ^= zobrist::piece_key(color, piece, field);
+                }
+            }
+        }
+    }
+    if position.side_to_move() == Color::Black {
+        key ^= zobrist::side_key();
+    }
+    let incremental = (position.key(), position.pawn_key());
+    match incremental == (key, pawn_key) {
+        true => Ok(()),
+        false => Err(Inconsistency::Keys {
+            incremental,
+            full: (key, pawn_key),
+        }),
+    }
+}
+
+/// Checks that the `evaluator`, with whatever it has cached, gives the same score as the evaluation from scratch.
+///
+/// The evaluator must use the hand-crafted evaluation for this position, e.g. with [EvalMode::Classical].
+pub fn check_cached(evaluator: &mut Evaluator, position: &Position) -> Result<(), Inconsistency> {
+    debug_assert!(
+        !evaluator.uses_network(position),
+        "the cached evaluation can only be checked without the network"
+    );
+    let cached = evaluator.evaluate(position);
+    let full = evaluate_with(position, evaluator.params());
+    match cached == full {
+        true => Ok(()),
+        false => Err(Inconsistency::Cached { cached, full }),
+    }
+}
+
+/// Runs all the checks with the default parameters on each of the `positions`.
+///
+/// Returns the indexes of the positions breaking any invariants, with the first broken one.
+/// The positions are evaluated with the same evaluator one after another, so the cached
+/// entries of the earlier ones are checked against the later ones too. The evaluator doesn't
+/// use the network, even when one is embedded.
+pub fn check_all<'a>(
+    positions: impl IntoIterator<Item = &'a Position>,
+) -> Vec<(usize, Inconsistency)> {
+    let mut evaluator = Evaluator::default();
+    evaluator.set_mode(EvalMode::Classical);
+    positions
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, position)| {
+            check_incremental(position)
+                .and_then(|_| check_symmetry(position, &DEFAULT_PARAMS))
+                .and_then(|_| check_cached(&mut evaluator, position))
+                .err()
+                .map(|inconsistency| (index, inconsistency))
+        })
+        .collect()
+}
+
+/// Generates `count` random positions with both kings and up to 30 other pieces, for running the checks on.
+///
+/// The positions don't have to be legal, and the same `seed` always gives the same ones.
+pub fn random_positions(seed: u64, count: usize) -> Vec<Position> {
+    let mut state = seed | 1;
+    let mut random = |limit: u64| {
+        let (next, value) = zobrist::next(state);
+        state = next;
+        value % limit
+    };
+    (0..count)
+        .map(|_| {
+            let mut position = Position::new();
+            for color in Color::iter() {
+                loop {
+                    let field = Field::from(random(64) as u8);
+                    if position.piece_at(field).is_none() {
+                        position.put(color, Piece::King, field);
+                        break;
+                    }
+                }
+            }
+            for _ in 0..random(31) {
+                let field = Field::from(random(64) as u8);
+                let color = Color::from_repr(random(2) as u8).unwrap();
+                let piece = Piece::from_repr(random(5) as u8).unwrap();
+                let pawn_rank = matches!(field.rank() as u8, 0 | 7);
+                if position.piece_at(field).is_none() && !(piece == Piece::Pawn && pawn_rank) {
+                    position.put(color, piece, field);
+                }
+            }
+            if random(2) == 1 {
+                position.set_side_to_move(Color::Black);
+            }
+            position
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_positions_are_consistent() {
+        let positions = random_positions(1, 500);
+        assert_eq!(check_all(&positions), []);
+        assert_eq!(positions, random_positions(1, 500));
+        assert_ne!(positions, random_positions(2, 500));
+        for position in &positions {
+            for color in Color::iter() {
+                assert_eq!(position.pieces(color, Piece::King).count(), 1);
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Win, draw and loss model of the scores.
+//!
+//! The raw evaluation scores don't have a fixed meaning: the same score gives different
+//! chances in the middlegame and in the endgame, and changes with every retuning.
+//! The model maps them to the win probability, and normalizes them so 100 centipawns
+//! always mean a 50% chance of winning.
+//!
+//! The probabilities need the floating point functions of `std`, the normalization doesn't.
+//!
+
+use super::{phase, PhaseScore, Score};
+use crate::Position;
+use core::fmt::Display;
+
+/// Parameters of the logistic win probability model.
+///
+/// The win probability of a raw `score` is `1 / (1 + exp((even - score) / spread))`,
+/// with both parameters tapered by the game phase. They should be fitted to the results
+/// of games played by the engine.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WdlParams {
+    /// Raw score with a 50% chance of winning.
+    pub even: PhaseScore,
+    /// How fast the win probability grows with the score.
+    pub spread: PhaseScore,
+}
+
+impl WdlParams {
+    /// The default parameters.
+    pub const DEFAULT: Self = Self {
+        even: PhaseScore::new(200, 180),
+        spread: PhaseScore::new(45, 35),
+    };
+}
+
+impl Default for WdlParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Chances of the side to move, in permille.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Wdl {
+    pub win: u32,
+    pub draw: u32,
+    pub loss: u32,
+}
+
+/// Formats the chances as the `wdl` field of the UCI `info` line.
+impl Display for Wdl {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "wdl {} {} {}", self.win, self.draw, self.loss)
+    }
+}
+
+/// Returns the probability of winning with the raw `score` for the side to move.
+#[cfg(feature = "std")]
+pub fn win_probability(score: Score, position: &Position, params: &WdlParams) -> f64 {
+    let phase = phase(position);
+    let even = params.even.taper(phase) as f64;
+    let spread = params.spread.taper(phase).max(1) as f64;
+    1.0 / (1.0 + ((even - score as f64) / spread).exp())
+}
+
+/// Returns the chances of winning, drawing and losing with the raw `score` for the side to move.
+#[cfg(feature = "std")]
+pub fn wdl(score: Score, position: &Position, params: &WdlParams) -> Wdl {
+    let win = (1000.0 * win_probability(score, position, params)).round() as u32;
+    let loss = (1000.0 * win_probability(-score, position, params)).round() as u32;
+    Wdl {
+        win,
+        draw: 1000u32.saturating_sub(win + loss),
+        loss,
+    }
+}
+
+/// Converts the raw `score` to the centipawns reported to the user, where 100 means a 50% chance of winning.
+///
+/// Only the reported scores should be normalized, the search works on the raw ones.
+pub fn normalize(score: Score, position: &Position, params: &WdlParams) -> Score {
+    let even = params.even.taper(phase(position)).max(1);
+    ((score as i64 * 100) / even as i64) as Score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn chances_add_up_and_grow_with_score() {
+        let params = WdlParams::DEFAULT;
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+            "8/8/4k3/8/2P5/8/5K2/8 w - - 0 1",
+        ] {
+            let position = Position::from_fen(fen).unwrap();
+            let chances: Vec<Wdl> = (-2000..=2000)
+                .step_by(5)
+                .map(|score| wdl(score, &position, &params))
+                .collect();
+            for wdl in &chances {
+                assert_eq!(wdl.win + wdl.draw + wdl.loss, 1000, "{wdl}");
+            }
+            for pair in chances.windows(2) {
+                assert!(pair[0].win <= pair[1].win, "{} {}", pair[0], pair[1]);
+                assert!(pair[0].loss >= pair[1].loss, "{} {}", pair[0], pair[1]);
+            }
+            assert_eq!(chances.first().unwrap().loss, 1000);
+            assert_eq!(chances.last().unwrap().win, 1000);
+            assert_eq!(
+                wdl(0, &position, &params).win,
+                wdl(0, &position, &params).loss
+            );
+        }
+    }
+
+    #[test]
+    fn normalized_scores() {
+        let params = WdlParams::DEFAULT;
+        let start =
+            Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1").unwrap();
+        assert_eq!(normalize(200, &start, &params), 100);
+        assert_eq!(normalize(-200, &start, &params), -100);
+        assert_eq!(normalize(0, &start, &params), 0);
+    }
+}
@@ -0,0 +1,388 @@
+//! Named access to all the evaluation weights.
+//!
+//! Each weight gets a dotted name built from the field names of the parameter structs,
+//! e.g. `pawns.doubled.mg` or `psqt.eg.knight.e4`, so the weights can be listed,
+//! changed one by one and saved by the tuners and the engine options.
+//!
+//! Looking a weight up by the name goes through all of them, so the callers changing many weights
+//! find their slots once with a [WeightIndex] and use the slots after that.
+//!
+
+use super::{EvalParams, PhaseScore, Score};
+use crate::Piece;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{Display, Write};
+use strum::IntoEnumIterator;
+
+impl EvalParams {
+    /// Calls the `visit` with the name and the value of each weight, which can be changed in place.
+    ///
+    /// The weights are always visited in the same order, and their positions in it are the slots
+    /// used by [EvalParams::weight_at] and [EvalParams::set_weight_at].
+    pub fn visit_weights(&mut self, visit: &mut dyn FnMut(&str, &mut Score)) {
+        let mut buffer = String::new();
+        self.walk(&mut |name, value| {
+            buffer.clear();
+            let _ = write!(buffer, "{name}");
+            visit(&buffer, value);
+        });
+    }
+
+    /// Calls the `visit` with each weight in the [EvalParams::visit_weights] order,
+    /// with the names written only if the `visit` needs them.
+    fn walk(&mut self, visit: &mut dyn FnMut(&dyn Display, &mut Score)) {
+        for piece in Piece::iter().filter(|&piece| piece != Piece::King) {
+            let score = &mut self.piece_values.values[piece as usize];
+            phase_score(
+                &format_args!("piece_values.{}", piece_name(piece)),
+                score,
+                visit,
+            );
+        }
+        for piece in Piece::iter() {
+            for index in 0..64 {
+                let (name, field) = (piece_name(piece), FieldName(index));
+                let mg = &mut self.psqt.mg[piece as usize][index];
+                visit(&format_args!("psqt.mg.{name}.{field}"), mg);
+                let eg = &mut self.psqt.eg[piece as usize][index];
+                visit(&format_args!("psqt.eg.{name}.{field}"), eg);
+            }
+        }
+
+        let pawns = &mut self.pawns;
+        phase_score(&"pawns.doubled", &mut pawns.doubled, visit);
+        phase_score(&"pawns.isolated", &mut pawns.isolated, visit);
+        phase_score(&"pawns.backward", &mut pawns.backward, visit);
+        phase_score(&"pawns.connected", &mut pawns.connected, visit);
+
+        let passed = &mut self.passed;
+        for (rank, score) in passed.rank.iter_mut().enumerate() {
+            phase_score(&format_args!("passed.rank.{}", rank + 1), score, visit);
+        }
+        phase_score(&"passed.blocked", &mut passed.blocked, visit);
+        phase_score(&"passed.king_proximity", &mut passed.king_proximity, visit);
+        phase_score(&"passed.rook_behind", &mut passed.rook_behind, visit);
+
+        let king = &mut self.king_safety;
+        phase_score(
+            &"king_safety.shield_missing",
+            &mut king.shield_missing,
+            visit,
+        );
+        phase_score(
+            &"king_safety.shield_advanced",
+            &mut king.shield_advanced,
+            visit,
+        );
+        phase_score(
+            &"king_safety.semi_open_file",
+            &mut king.semi_open_file,
+            visit,
+        );
+        phase_score(&"king_safety.open_file", &mut king.open_file, visit);
+        for piece in Piece::iter() {
+            let units = &mut king.attack_units[piece as usize];
+            visit(
+                &format_args!("king_safety.attack_units.{}", piece_name(piece)),
+                units,
+            );
+        }
+        for (units, score) in king.danger.iter_mut().enumerate() {
+            visit(&format_args!("king_safety.danger.{units}"), score);
+        }
+
+        let mobility = &mut self.mobility;
+        let tables: [(&str, &mut [PhaseScore]); 4] = [
+            ("knight", &mut mobility.knight),
+            ("bishop", &mut mobility.bishop),
+            ("rook", &mut mobility.rook),
+            ("queen", &mut mobility.queen),
+        ];
+        for (piece, table) in tables {
+            for (moves, score) in table.iter_mut().enumerate() {
+                phase_score(&format_args!("mobility.{piece}.{moves}"), score, visit);
+            }
+        }
+
+        let imbalance = &mut self.imbalance;
+        phase_score(&"imbalance.bishop_pair", &mut imbalance.bishop_pair, visit);
+        phase_score(&"imbalance.knight_pair", &mut imbalance.knight_pair, visit);
+        phase_score(&"imbalance.rook_pair", &mut imbalance.rook_pair, visit);
+        phase_score(
+            &"imbalance.knight_pawns",
+            &mut imbalance.knight_pawns,
+            visit,
+        );
+        phase_score(
+            &"imbalance.bishop_pawns",
+            &mut imbalance.bishop_pawns,
+            visit,
+        );
+        phase_score(&"imbalance.rook_pawns", &mut imbalance.rook_pawns, visit);
+
+        let rooks = &mut self.rooks;
+        phase_score(&"rooks.open_file", &mut rooks.open_file, visit);
+        phase_score(&"rooks.semi_open_file", &mut rooks.semi_open_file, visit);
+        phase_score(&"rooks.seventh_rank", &mut rooks.seventh_rank, visit);
+        phase_score(&"rooks.connected", &mut rooks.connected, visit);
+
+        phase_score(&"outposts.knight", &mut self.outposts.knight, visit);
+        phase_score(&"outposts.bishop", &mut self.outposts.bishop, visit);
+
+        let threats = &mut self.threats;
+        for piece in Piece::iter() {
+            let name = piece_name(piece);
+            let by_minor = &mut threats.by_minor[piece as usize];
+            phase_score(&format_args!("threats.by_minor.{name}"), by_minor, visit);
+            let by_rook = &mut threats.by_rook[piece as usize];
+            phase_score(&format_args!("threats.by_rook.{name}"), by_rook, visit);
+        }
+        phase_score(&"threats.hanging", &mut threats.hanging, visit);
+
+        phase_score(&"space.per_field", &mut self.space.per_field, visit);
+
+        let scale = &mut self.scale;
+        visit(&"scale.opposite_bishops", &mut scale.opposite_bishops);
+        visit(
+            &"scale.opposite_bishops_with_pieces",
+            &mut scale.opposite_bishops_with_pieces,
+        );
+        visit(&"scale.rook_ending", &mut scale.rook_ending);
+        visit(&"scale.fifty_move_decay", &mut scale.fifty_move_decay);
+
+        visit(&"mop_up.threshold", &mut self.mop_up.threshold);
+        visit(&"mop_up.corner", &mut self.mop_up.corner);
+        visit(&"mop_up.proximity", &mut self.mop_up.proximity);
+
+        visit(&"lazy.margin", &mut self.lazy.margin);
+        phase_score(&"contempt.contempt", &mut self.contempt.contempt, visit);
+        visit(&"hybrid.imbalance", &mut self.hybrid.imbalance);
+        phase_score(&"wdl.even", &mut self.wdl.even, visit);
+        phase_score(&"wdl.spread", &mut self.wdl.spread, visit);
+    }
+
+    /// Returns the names and the values of all the weights, in the [EvalParams::visit_weights] order.
+    pub fn weights(&self) -> Vec<(String, Score)> {
+        let mut weights = Vec::new();
+        self.clone()
+            .visit_weights(&mut |name, value| weights.push((name.to_string(), *value)));
+        weights
+    }
+
+    /// Returns value of the weight with the `name`, if there is one.
+    ///
+    /// This goes through all the weights, so for many lookups find their slots once with [WeightIndex]
+    /// and use [EvalParams::weight_at].
+    pub fn weight(&self, name: &str) -> Option<Score> {
+        let mut found = None;
+        self.clone().walk(&mut |weight, value| {
+            if found.is_none() && is_named(weight, name) {
+                found = Some(*value);
+            }
+        });
+        found
+    }
+
+    /// Changes value of the weight with the `name`, returning `false` if there is no such weight.
+    ///
+    /// Like [EvalParams::weight], this goes through all the weights, see [EvalParams::set_weight_at].
+    pub fn set_weight(&mut self, name: &str, value: Score) -> bool {
+        let mut found = false;
+        self.walk(&mut |weight, old| {
+            if !found && is_named(weight, name) {
+                *old = value;
+                found = true;
+            }
+        });
+        found
+    }
+
+    /// Calls the `visit` with the slot and the value of each weight, without building their names.
+    pub fn visit_slots(&mut self, visit: &mut dyn FnMut(usize, &mut Score)) {
+        let mut slot = 0;
+        self.walk(&mut |_, value| {
+            visit(slot, value);
+            slot += 1;
+        });
+    }
+
+    /// Returns value of the weight in the `slot` of the [EvalParams::visit_weights] order.
+    pub fn weight_at(&self, slot: usize) -> Option<Score> {
+        let mut found = None;
+        self.clone().visit_slots(&mut |index, value| {
+            if index == slot {
+                found = Some(*value);
+            }
+        });
+        found
+    }
+
+    /// Changes value of the weight in the `slot`, returning `false` if there are fewer weights.
+    pub fn set_weight_at(&mut self, slot: usize, value: Score) -> bool {
+        let mut found = false;
+        self.visit_slots(&mut |index, old| {
+            if index == slot {
+                *old = value;
+                found = true;
+            }
+        });
+        found
+    }
+
+    /// Changes the weights in the given slots in a single pass over all the weights.
+    ///
+    /// The slots can come in any order; the slots past the last weight are skipped,
+    /// and for a repeated slot the last value wins.
+    pub fn set_weights_at(&mut self, weights: impl IntoIterator<Item = (usize, Score)>) {
+        let mut weights: Vec<(usize, Score)> = weights.into_iter().collect();
+        weights.sort_by_key(|&(slot, _)| slot);
+        let mut pending = weights.into_iter().peekable();
+        self.visit_slots(&mut |slot, old| {
+            while let Some((next, value)) = pending.next_if(|&(next, _)| next <= slot) {
+                if next == slot {
+                    *old = value;
+                }
+            }
+        });
+    }
+}
+
+/// Slots of the weights by their names, built once for looking up many of them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeightIndex {
+    /// The names with their slots, sorted by the names.
+    slots: Vec<(String, usize)>,
+}
+
+impl WeightIndex {
+    /// Collects the names of all the weights, which are the same for any [EvalParams].
+    pub fn new() -> Self {
+        let mut slots = Vec::new();
+        let mut params = EvalParams::DEFAULT;
+        params.visit_weights(&mut |name, _| {
+            slots.push((name.to_string(), slots.len()));
+        });
+        slots.sort_unstable();
+        Self { slots }
+    }
+
+    /// Returns the slot of the weight with the `name`, for [EvalParams::weight_at] and [EvalParams::set_weight_at].
+    pub fn slot(&self, name: &str) -> Option<usize> {
+        let found = self
+            .slots
+            .binary_search_by(|(weight, _)| weight.as_str().cmp(name));
+        found.ok().map(|index| self.slots[index].1)
+    }
+
+    /// Returns the number of the weights.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Checks if there are no weights, which never happens.
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+}
+
+impl Default for WeightIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Visits both the middlegame and the endgame part of the `score`.
+fn phase_score(
+    name: &dyn Display,
+    score: &mut PhaseScore,
+    visit: &mut dyn FnMut(&dyn Display, &mut Score),
+) {
+    visit(&format_args!("{name}.mg"), &mut score.mg);
+    visit(&format_args!("{name}.eg"), &mut score.eg);
+}
+
+/// Checks if the written `weight` name is the `name`, without building it.
+fn is_named(weight: &dyn Display, name: &str) -> bool {
+    /// Takes the written text off the front of the expected one, failing on a difference.
+    struct Matcher<'a>(&'a str);
+
+    impl Write for Matcher<'_> {
+        fn write_str(&mut self, text: &str) -> core::fmt::Result {
+            self.0 = self.0.strip_prefix(text).ok_or(core::fmt::Error)?;
+            Ok(())
+        }
+    }
+
+    let mut matcher = Matcher(name);
+    write!(matcher, "{weight}").is_ok() && matcher.0.is_empty()
+}
+
+/// Returns the lowercase name of the `piece`.
+fn piece_name(piece: Piece) -> &'static str {
+    match piece {
+        Piece::Pawn => "pawn",
+        Piece::Knight => "knight",
+        Piece::Bishop => "bishop",
+        Piece::Rook => "rook",
+        Piece::Queen => "queen",
+        Piece::King => "king",
+    }
+}
+
+/// Lowercase name of the field with the index, like `e4`.
+struct FieldName(usize);
+
+impl Display for FieldName {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let file = (b'a' + (self.0 % 8) as u8) as char;
+        let rank = (b'1' + (self.0 / 8) as u8) as char;
+        write!(f, "{file}{rank}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_lookups() {
+        let mut params = EvalParams::DEFAULT;
+        let weights = params.weights();
+        let index = WeightIndex::new();
+        assert_eq!(index.len(), weights.len());
+        for (slot, (name, value)) in weights.iter().enumerate() {
+            assert_eq!(index.slot(name), Some(slot), "{name}");
+            assert_eq!(params.weight(name), Some(*value), "{name}");
+            assert_eq!(params.weight_at(slot), Some(*value), "{name}");
+        }
+        assert_eq!(index.slot("psqt.mg.knight"), None);
+        assert_eq!(params.weight("psqt.mg.knight"), None);
+        assert_eq!(params.weight("psqt.mg.knight.e44"), None);
+        assert_eq!(params.weight_at(weights.len()), None);
+
+        assert!(params.set_weight("psqt.eg.knight.e4", 17));
+        assert!(!params.set_weight("psqt.eg.knight.e", 17));
+        let slot = index.slot("psqt.eg.knight.e4").unwrap();
+        assert_eq!(params.weight_at(slot), Some(17));
+        assert!(params.set_weight_at(slot, 18));
+        assert_eq!(params.weight("psqt.eg.knight.e4"), Some(18));
+        assert!(!params.set_weight_at(weights.len(), 18));
+    }
+
+    #[test]
+    fn set_many_weights() {
+        let mut params = EvalParams::DEFAULT;
+        let last = params.weights().len() - 1;
+        params.set_weights_at([(last, 3), (0, 1), (5, 2), (0, 4), (last + 1, 5)]);
+        assert_eq!(params.weight_at(0), Some(4));
+        assert_eq!(params.weight_at(5), Some(2));
+        assert_eq!(params.weight_at(last), Some(3));
+        let defaults = EvalParams::DEFAULT.weights();
+        for (slot, (weight, default)) in params.weights().iter().zip(&defaults).enumerate() {
+            if ![0, 5, last].contains(&slot) {
+                assert_eq!(weight, default);
+            }
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! Entry points for fuzzing the parsers.
+//!
+//! Each function takes arbitrary bytes, feeds them to a parser, and checks what was parsed.
+//! The parsers must never panic on malformed input, so a panic here is a bug: either in the parser,
+//! or in the round trip through the matching writer. The `fuzz` directory has the `cargo fuzz`
+//! targets calling them, with the `fuzz` feature enabling this module.
+//!
+
+use crate::nnue::Network;
+use crate::Position;
+
+/// Parses the data as a FEN, and checks the parsed position survives writing it back.
+pub fn fen(data: &[u8]) {
+    let Ok(fen) = core::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(position) = Position::from_fen(fen) {
+        let written = position.to_fen();
+        assert_eq!(Position::from_fen(&written), Ok(position), "{written}");
+    }
+}
+
+/// Parses the data as a network file, and checks the parsed network survives writing it back
+/// and can evaluate a position.
+pub fn network(data: &[u8]) {
+    if let Ok(network) = Network::from_bytes(data) {
+        assert_eq!(network.to_bytes(), data);
+        network.evaluate(&Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap());
+    }
+}
+
+/// Parses the data as a tuning sample line, a FEN followed by the game result.
+#[cfg(feature = "std")]
+pub fn sample(data: &[u8]) {
+    let Ok(line) = core::str::from_utf8(data) else {
+        return;
+    };
+    if let Some(sample) = crate::tune::Sample::parse(line) {
+        assert!((0.0..=1.0).contains(&sample.result));
+    }
+}
+
+/// Parses the data as a line written by a UCI engine.
+#[cfg(feature = "std")]
+pub fn uci_message(data: &[u8]) {
+    if let Ok(line) = core::str::from_utf8(data) {
+        crate::uci::Message::parse(line);
+    }
+}
+
+/// Handles the data as a `setoption` command, on the options registered by the evaluator.
+pub fn setoption(data: &[u8]) {
+    let Ok(command) = core::str::from_utf8(data) else {
+        return;
+    };
+    let mut options = crate::options::Options::new();
+    crate::eval::Evaluator::register_options(&mut options);
+    if let Ok(name) = options.set_from_command(command) {
+        assert!(options.get(&name).is_some());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nnue::tests::random_network;
+    use crate::nnue::PieceSquare;
+
+    #[test]
+    fn seed_corpus() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+            "4k3/8/8/8/8/8/8/4KQ2 b - - 99 80",
+            "4k3/8/8/8/8/8/8/4KQ2 w - - 3000000000 1",
+            "8/8/8/8/8/8/8/8 w",
+            "rnbqkbnr/ppppXppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+            "9/8 w",
+            "",
+        ];
+        for data in fens
+            .map(str::as_bytes)
+            .into_iter()
+            .chain([&[0xff, 0xfe][..]])
+        {
+            fen(data);
+        }
+
+        let bytes = random_network(8, &PieceSquare, 8, &[4, 1]).to_bytes();
+        network(&bytes);
+        for len in [0, 1, 7, bytes.len() / 2, bytes.len() - 1] {
+            network(&bytes[..len]);
+        }
+
+        for command in [
+            "setoption name Contempt value 20",
+            "setoption name Contempt value 1000",
+            "setoption name Contempt value x",
+            "setoption name Clear Hash",
+            "setoption name UCI_ShowWDL value true",
+            "setoption name Unknown value 1",
+            "setoption name",
+            "setoption",
+            "",
+        ] {
+            setoption(command.as_bytes());
+        }
+
+        #[cfg(feature = "std")]
+        {
+            for line in [
+                "4k3/8/8/8/8/8/8/4KQ2 w - - 0 1 1-0",
+                "4k3/8/8/8/8/8/8/4KQ2 w - - 0 1 c9 \"1/2-1/2\";",
+                "4k3/8/8/8/8/8/8/4KQ2 w - - 0 1 [2.5]",
+                "4k3/8/8/8/8/8/8/4KQ2 w - - 0 1",
+                "",
+            ] {
+                sample(line.as_bytes());
+            }
+            for line in [
+                "uciok",
+                "bestmove e2e4 ponder e7e5",
+                "info depth 5 score cp 20 nodes 1000 pv e2e4 e7e5",
+                "info score mate",
+                "option name Hash type spin default 16 min 1 max 33554432",
+                "option name type",
+                "",
+            ] {
+                uci_message(line.as_bytes());
+            }
+        }
+    }
+}
@@ -2,17 +2,48 @@
 //!
 //! Basically it's my playground for learning Rust.
 //!
+//! Without the default `std` feature the crate is `no_std` and needs only `alloc`.
+//! The parts using threads, files, processes or the clock are left out then:
+//! the [time] manager, the [tune]r, the [uci] client and loading networks from files.
+//!
+//! The `fuzz` feature adds the entry points for the fuzzing targets, left out otherwise
+//! as they panic on the bugs they find.
+//!
 
-use num_enum::TryFromPrimitive;
-use num_integer::div_rem;
-use std::convert::TryFrom;
-use std::fmt::Display;
-use std::iter::Iterator;
-use std::marker::PhantomData;
-use std::ops::{
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod attacks;
+mod error;
+pub mod eval;
+#[cfg(any(feature = "fuzz", test))]
+pub mod fuzz;
+pub mod nnue;
+pub mod options;
+mod position;
+pub mod svg;
+pub mod testing;
+#[cfg(feature = "std")]
+pub mod time;
+#[cfg(feature = "std")]
+pub mod tune;
+#[cfg(feature = "std")]
+pub mod uci;
+pub mod zobrist;
+
+pub use error::Error;
+pub use position::{FenError, Position, MAX_HALFMOVE_CLOCK};
+
+use alloc::string::String;
+use core::fmt::Display;
+use core::iter::Iterator;
+use core::marker::PhantomData;
+use core::ops::{
     Add, BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, RangeInclusive,
 };
-use strum_macros::FromRepr;
+use num_integer::div_rem;
+use strum_macros::{EnumIter, FromRepr};
 
 /// Trait for storing allowed values for a type.
 ///
@@ -64,9 +95,16 @@ impl From<i8> for File {
 
 impl From<u8> for File {
     fn from(value: u8) -> Self {
-        match value {
-            _ if !Self::is_valid(value) => Self::INVALID,
-            _ => Self::from_repr(value).unwrap(),
+        Self::from_index(value)
+    }
+}
+
+impl File {
+    /// Returns the file with the `index`, counted from 0 for the file A, or `INVALID`.
+    pub const fn from_index(index: u8) -> Self {
+        match Self::from_repr(index) {
+            Some(file) => file,
+            None => Self::INVALID,
         }
     }
 }
@@ -105,17 +143,32 @@ impl From<i8> for Rank {
 
 impl From<u8> for Rank {
     fn from(value: u8) -> Self {
-        match value {
-            _ if !Self::is_valid(value) => Self::INVALID,
-            _ => Self::from_repr(value).unwrap(),
+        Self::from_index(value)
+    }
+}
+
+impl Rank {
+    /// Returns the rank with the `index`, counted from 0 for the rank 1, or `INVALID`.
+    pub const fn from_index(index: u8) -> Self {
+        match Self::from_repr(index) {
+            Some(rank) => rank,
+            None => Self::INVALID,
+        }
+    }
+
+    /// Returns the rank as seen by the `color` player, so `Rank1` is always the player's first rank.
+    pub const fn relative(self, color: Color) -> Rank {
+        match (self, color) {
+            (Rank::INVALID, _) | (_, Color::White) => self,
+            (_, Color::Black) => Rank::from_index(7 - self as u8),
         }
     }
 }
 
 /// Field coordinates as numbers.
 pub struct Point {
-    file: u8,
-    rank: u8,
+    pub file: u8,
+    pub rank: u8,
 }
 
 /// Board fields.
@@ -152,28 +205,14 @@ impl From<i8> for Field {
 
 impl From<u8> for Field {
     fn from(value: u8) -> Self {
-        match value {
-            _ if !Self::is_valid(value) => Self::INVALID,
-            _ => Self::from_repr(value).unwrap(),
-        }
+        Self::from_index(value)
     }
 }
 
 impl From<Field> for Point {
     fn from(value: Field) -> Self {
         let (d, m) = div_rem(value as u8, 8);
-        Point { file: d, rank: m }
-    }
-}
-
-/// Type for storing move vector from one field to another.
-struct MoveVector(i8, i8);
-
-impl Add for MoveVector {
-    type Output = Self;
-
-    fn add(self, rhs: Self) -> Self::Output {
-        MoveVector(self.0 + rhs.0, self.1 + rhs.1)
+        Point { file: m, rank: d }
     }
 }
 
@@ -224,36 +263,16 @@ pub enum Direction {
    SSWW,  SSW,  SS,  SSE,  SSEE,
 }
 
-impl From<Direction> for MoveVector {
+impl Direction {
+    /// Returns the numbers of files and ranks to move in the direction.
     #[rustfmt::skip]
-    #[inline(always)]
-    fn from(value: Direction) -> Self {
-        match value {
-            Direction::H => Self( 0,  0),
-            Direction::N => Self( 0,  1),
-            Direction::E => Self( 1,  0),
-            Direction::S => Self( 0, -1),
-            Direction::W => Self(-1,  0),
-            Direction::EE => Self::from(Direction::E) + Self::from(Direction::E),
-            Direction::WW => Self::from(Direction::W) + Self::from(Direction::W),
-            Direction::NN => Self::from(Direction::N) + Self::from(Direction::N),
-            Direction::NE => Self::from(Direction::N) + Self::from(Direction::E),
-            Direction::NW => Self::from(Direction::N) + Self::from(Direction::W),
-            Direction::SS => Self::from(Direction::S) + Self::from(Direction::S),
-            Direction::SE => Self::from(Direction::S) + Self::from(Direction::E),
-            Direction::SW => Self::from(Direction::S) + Self::from(Direction::W),
-            Direction::NNW => Self::from(Direction::NN) + Self::from(Direction::W),
-            Direction::NNE => Self::from(Direction::NN) + Self::from(Direction::E),
-            Direction::NWW => Self::from(Direction::N) + Self::from(Direction::WW),
-            Direction::NEE => Self::from(Direction::N) + Self::from(Direction::EE),
-            Direction::SWW => Self::from(Direction::S) + Self::from(Direction::WW),
-            Direction::SEE => Self::from(Direction::S) + Self::from(Direction::EE),
-            Direction::SSW => Self::from(Direction::SS) + Self::from(Direction::W),
-            Direction::SSE => Self::from(Direction::SS) + Self::from(Direction::E),
-            Direction::NNWW => Self::from(Direction::NN) + Self::from(Direction::WW),
-            Direction::NNEE => Self::from(Direction::NN) + Self::from(Direction::EE),
-            Direction::SSWW => Self::from(Direction::SS) + Self::from(Direction::WW),
-            Direction::SSEE => Self::from(Direction::SS) + Self::from(Direction::EE),
+    pub const fn vector(self) -> (i8, i8) {
+        match self {
+            Direction::NNWW => (-2,  2), Direction::NNW => (-1,  2), Direction::NN => (0,  2), Direction::NNE => (1,  2), Direction::NNEE => (2,  2),
+            Direction::NWW  => (-2,  1), Direction::NW  => (-1,  1), Direction::N  => (0,  1), Direction::NE  => (1,  1), Direction::NEE  => (2,  1),
+            Direction::WW   => (-2,  0), Direction::W   => (-1,  0), Direction::H  => (0,  0), Direction::E   => (1,  0), Direction::EE   => (2,  0),
+            Direction::SWW  => (-2, -1), Direction::SW  => (-1, -1), Direction::S  => (0, -1), Direction::SE  => (1, -1), Direction::SEE  => (2, -1),
+            Direction::SSWW => (-2, -2), Direction::SSW => (-1, -2), Direction::SS => (0, -2), Direction::SSE => (1, -2), Direction::SSEE => (2, -2),
         }
     }
 }
@@ -262,50 +281,107 @@ impl Add<Direction> for Field {
     type Output = Field;
 
     fn add(self, rhs: Direction) -> Self::Output {
-        let mv = MoveVector::from(rhs);
-        let file = self.file() as i8 + mv.0;
-        let rank = self.rank() as i8 + mv.1;
-
-        Self::new(File::from(file), Rank::from(rank))
+        self.mv(rhs)
     }
 }
 
 impl Field {
     /// Creates new Field from the arguments.
-    fn new(file: File, rank: Rank) -> Self {
-        if file == File::INVALID {
+    pub const fn new(file: File, rank: Rank) -> Self {
+        if matches!(file, File::INVALID) || matches!(rank, Rank::INVALID) {
             return Self::INVALID;
         }
-        if rank == Rank::INVALID {
-            return Self::INVALID;
-        }
-
-        match Field::from_repr(file as u8 + rank as u8) {
-            Some(x) => x,
-            None => Field::INVALID,
+        Self::from_index(file as u8 + 8 * rank as u8)
+    }
+    /// Returns the field with the `index`, counted from 0 for A1, or `INVALID`.
+    pub const fn from_index(index: u8) -> Self {
+        match Self::from_repr(index) {
+            Some(field) => field,
+            None => Self::INVALID,
         }
     }
     /// Calculates the Rank for the field.
-    fn rank(self) -> Rank {
-        match Rank::from_repr(self as u8 / 8) {
-            Some(x) => x,
-            None => Rank::INVALID,
+    pub const fn rank(self) -> Rank {
+        match self {
+            Field::INVALID => Rank::INVALID,
+            _ => Rank::from_index(self as u8 / 8),
         }
     }
-    fn file(self) -> File {
-        match File::from_repr(self as u8 / 8) {
-            Some(x) => x,
-            None => File::INVALID,
+    /// Calculates the File for the field.
+    pub const fn file(self) -> File {
+        match self {
+            Field::INVALID => File::INVALID,
+            _ => File::from_index(self as u8 % 8),
         }
     }
     /// Finds a new Field moving in the given `direction` from the current field.
-    fn mv(self, direction: Direction) -> Field {
-        self + direction
+    pub const fn mv(self, direction: Direction) -> Field {
+        if matches!(self, Field::INVALID) {
+            return Field::INVALID;
+        }
+        let (files, ranks) = direction.vector();
+        let file = self.file() as i8 + files;
+        let rank = self.rank() as i8 + ranks;
+        if file < 0 || file > 7 || rank < 0 || rank > 7 {
+            return Field::INVALID;
+        }
+        Self::new(File::from_index(file as u8), Rank::from_index(rank as u8))
+    }
+    /// Returns number of king moves needed to go from this field to the `other` one.
+    pub const fn distance(self, other: Field) -> u8 {
+        let files = (self.file() as i8 - other.file() as i8).unsigned_abs();
+        let ranks = (self.rank() as i8 - other.rank() as i8).unsigned_abs();
+        match files > ranks {
+            true => files,
+            false => ranks,
+        }
+    }
+    /// Returns the field as seen by the `color` player, mirroring the board vertically for black.
+    ///
+    /// This way tables written for white can be used for black too, e.g. E2 for black is E7.
+    pub const fn relative(self, color: Color) -> Field {
+        match (self, color) {
+            (Field::INVALID, _) | (_, Color::White) => self,
+            (_, Color::Black) => Field::from_index(self as u8 ^ 56),
+        }
+    }
+}
+
+/// Side playing the game.
+#[derive(Clone, Copy, FromRepr, EnumIter, Debug, Default, PartialEq)]
+#[repr(u8)]
+pub enum Color {
+    #[default]
+    White = 0,
+    Black = 1,
+}
+
+impl Not for Color {
+    type Output = Self;
+
+    /// Returns the opponent's color.
+    fn not(self) -> Self::Output {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
     }
 }
 
+/// Kinds of the chess pieces, regardless of their color.
+#[derive(Clone, Copy, FromRepr, EnumIter, Debug, PartialEq)]
+#[repr(u8)]
+pub enum Piece {
+    Pawn = 0,
+    Knight = 1,
+    Bishop = 2,
+    Rook = 3,
+    Queen = 4,
+    King = 5,
+}
+
 /// Bitboard.
-#[derive(Default)]
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
 pub struct Bitboard {
     board: u64,
     _phantom: PhantomData<usize>,
@@ -313,35 +389,127 @@ pub struct Bitboard {
 
 impl Bitboard {
     /// Creates a new Bitboard copying the `value` as the bits representation.
-    pub fn new(value: u64) -> Self {
+    pub const fn new(value: u64) -> Self {
         Self {
             board: value,
             _phantom: PhantomData,
         }
     }
-    /// Creates bit mask with just one bit set (specified as the `index`).
-    pub fn make_mask(index: u8) -> u64 {
-        1u64 << index
+    /// Creates bit mask with just one bit set (specified as the `index`), or an empty one for the index outside the board.
+    pub const fn make_mask(index: u8) -> u64 {
+        match 1u64.checked_shl(index as u32) {
+            Some(mask) => mask,
+            None => 0,
+        }
     }
-    /// Sets the bit for the given field.
+    /// Sets the bit for the given field, doing nothing for the invalid field.
     pub fn set(&mut self, field: Field) {
-        self.board &= Self::make_mask(field as u8)
+        self.board |= Self::make_mask(field as u8)
     }
 
-    /// Clears the bit for the given field.
+    /// Clears the bit for the given field, doing nothing for the invalid field.
     pub fn unset(&mut self, field: Field) {
         self.board &= !Self::make_mask(field as u8)
     }
 
     /// Checks if the bitboard has set the `field`.
-    pub fn is_set(&self, field: Field) -> bool {
+    pub const fn is_set(&self, field: Field) -> bool {
         self.get(field)
     }
 
     /// Returns value for the `field`.
-    pub fn get(&self, field: Field) -> bool {
+    pub const fn get(&self, field: Field) -> bool {
         0 != (self.board & Self::make_mask(field as u8))
     }
+
+    /// Returns the bits representation, with A1 as the lowest bit.
+    pub const fn bits(self) -> u64 {
+        self.board
+    }
+
+    /// Returns the number of the set fields.
+    pub const fn count(&self) -> u32 {
+        self.board.count_ones()
+    }
+
+    /// Checks if there are no set fields.
+    pub const fn is_empty(&self) -> bool {
+        self.board == 0
+    }
+}
+
+impl Bitboard {
+    /// Fields of the file A.
+    pub const FILE_A: u64 = 0x0101_0101_0101_0101;
+    /// Fields of the rank 1.
+    pub const RANK_1: u64 = 0xFF;
+    /// Light fields of the board, B1 is the first one.
+    pub const LIGHT_FIELDS: u64 = 0x55AA_55AA_55AA_55AA;
+
+    /// Returns bitboard with all the fields of the `file` set.
+    pub const fn file(file: File) -> Self {
+        match file {
+            File::INVALID => Self::new(0),
+            _ => Self::new(Self::FILE_A << file as u8),
+        }
+    }
+
+    /// Returns bitboard with all the fields of the `rank` set.
+    pub const fn rank(rank: Rank) -> Self {
+        match rank {
+            Rank::INVALID => Self::new(0),
+            _ => Self::new(Self::RANK_1 << (8 * rank as u8)),
+        }
+    }
+
+    /// Returns bitboard with the fields of the files next to the `file`.
+    pub const fn adjacent_files(file: File) -> Self {
+        let left = Self::file(File::from_index((file as u8).wrapping_sub(1)));
+        let right = Self::file(File::from_index((file as u8).wrapping_add(1)));
+        Self::new(left.board | right.board)
+    }
+
+    /// Returns bitboard with the fields of all the ranks in front of the `rank`,
+    /// as seen by the `color` player.
+    pub const fn ranks_in_front(rank: Rank, color: Color) -> Self {
+        match (rank, color) {
+            (Rank::INVALID, _) | (Rank::Rank8, Color::White) | (Rank::Rank1, Color::Black) => {
+                Self::new(0)
+            }
+            (_, Color::White) => Self::new(!0u64 << (8 * (rank as u8 + 1))),
+            (_, Color::Black) => Self::new((1u64 << (8 * rank as u8)) - 1),
+        }
+    }
+
+    /// Moves all the set fields in the `direction`.
+    ///
+    /// The fields which would leave the board are dropped, instead of wrapping around to the other side.
+    pub const fn shift(self, direction: Direction) -> Self {
+        let (files, ranks) = direction.vector();
+        let mut board = self.board;
+        let mut file = 0;
+        while file < 8 {
+            if file + files < 0 || file + files > 7 {
+                board &= !(Self::FILE_A << file);
+            }
+            file += 1;
+        }
+        let offset = files + 8 * ranks;
+        match offset >= 0 {
+            true => Self::new(board << offset),
+            false => Self::new(board >> -offset),
+        }
+    }
+}
+
+impl From<Field> for Bitboard {
+    /// Creates bitboard with just the `field` set, or an empty one for the invalid field.
+    fn from(field: Field) -> Self {
+        match field {
+            Field::INVALID => Self::default(),
+            _ => Self::new(Self::make_mask(field as u8)),
+        }
+    }
 }
 
 impl BitOr for Bitboard {
@@ -388,76 +556,73 @@ impl Not for Bitboard {
     }
 }
 
-/// Private struct used for implementing iterator for the set fields.
-struct SetFields<'a> {
+/// Iterator over the set fields of a bitboard, from A1 to H8.
+pub struct SetFields<'a> {
     board: &'a Bitboard,
     current: u8,
 }
 
-/// Private struct used for implementing iterator for the not set fields.
-struct UnsetFields<'a> {
+/// Iterator over the not set fields of a bitboard, from A1 to H8.
+pub struct UnsetFields<'a> {
     board: &'a Bitboard,
     current: u8,
 }
 
 impl<'a> SetFields<'a> {
-    fn new(board: &Bitboard) -> SetFields {
+    fn new(board: &'a Bitboard) -> Self {
         SetFields { board, current: 0 }
     }
 }
 
-impl<'a> Iterator for SetFields<'a> {
+impl Iterator for SetFields<'_> {
     type Item = Field;
 
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            self.current = self.current + 1;
-            if !File::is_valid(self.current) {
-                return Option::None;
-            }
+        while Field::is_valid(self.current) {
             let field = Field::from(self.current);
-            match self.board.get(field) == true {
-                false => continue,
-                true => return Option::Some(field),
+            self.current += 1;
+            if self.board.get(field) {
+                return Option::Some(field);
             }
         }
+        Option::None
     }
 }
 
 impl<'a> UnsetFields<'a> {
-    fn new(board: &Bitboard) -> UnsetFields {
+    fn new(board: &'a Bitboard) -> Self {
         UnsetFields { board, current: 0 }
     }
 }
 
-impl<'a> Iterator for UnsetFields<'a> {
+impl Iterator for UnsetFields<'_> {
     type Item = Field;
 
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            self.current = self.current + 1;
-            if !File::is_valid(self.current) {
-                return Option::None;
-            }
+        while Field::is_valid(self.current) {
             let field = Field::from(self.current);
-            match self.board.get(field) == false {
-                false => continue,
-                true => return Option::Some(field),
+            self.current += 1;
+            if !self.board.get(field) {
+                return Option::Some(field);
             }
         }
+        Option::None
     }
 }
 
 impl Bitboard {
-    fn set_fields_iter(&self) -> SetFields {
+    /// Returns iterator over the set fields.
+    pub fn set_fields_iter(&self) -> SetFields<'_> {
         SetFields::new(self)
     }
-    fn unset_fields_iter(&self) -> UnsetFields {
+    /// Returns iterator over the not set fields.
+    pub fn unset_fields_iter(&self) -> UnsetFields<'_> {
         UnsetFields::new(self)
     }
 }
 
 // -------------------------------------------------
+/// Settings for printing a bitboard as text.
 struct FormatterFlags {
     show_board: bool,
     show_files: bool,
@@ -474,25 +639,188 @@ impl Default for FormatterFlags {
             show_board: true,
             show_files: true,
             show_ranks: true,
-            files: ['1', '2', '3', '4', '5', '6', '7', '8'],
-            ranks: ['A', 'B', 'C', 'D', 'E', 'F', 'G', 'H'],
-            empty_field: ' ',
+            files: ['A', 'B', 'C', 'D', 'E', 'F', 'G', 'H'],
+            ranks: ['1', '2', '3', '4', '5', '6', '7', '8'],
+            empty_field: '.',
             not_empty_field: 'x',
         }
     }
 }
 
 impl Bitboard {
+    /// Draws the board with the rank 8 at the top, the way it's seen by the white player.
     fn as_string(&self, ff: FormatterFlags) -> String {
-        " ".to_string()
-    }
-}
-
-macro_rules! assert_eq {
-    ($one:tt, $two:tt) => {
-        if $one != $two {
-            let diff = $one ^ $two;
-            print!(diff.to_string());
+        let mut out = String::new();
+        if ff.show_board {
+            for rank in (0..8u8).rev() {
+                if ff.show_ranks {
+                    out.push(ff.ranks[rank as usize]);
+                    out.push(' ');
+                }
+                for file in 0..8u8 {
+                    let field = Field::new(File::from(file), Rank::from(rank));
+                    out.push(match self.is_set(field) {
+                        true => ff.not_empty_field,
+                        false => ff.empty_field,
+                    });
+                }
+                out.push('\n');
+            }
         }
-    };
+        if ff.show_files {
+            if ff.show_ranks {
+                out.push_str("  ");
+            }
+            out.extend(ff.files.iter());
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl Display for Bitboard {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.as_string(FormatterFlags::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn field_coordinates() {
+        assert_eq!(Field::new(File::FileE, Rank::Rank2), Field::E2);
+        assert_eq!(Field::new(File::FileH, Rank::Rank8), Field::H8);
+        assert_eq!(Field::new(File::INVALID, Rank::Rank1), Field::INVALID);
+        assert_eq!(Field::new(File::FileA, Rank::INVALID), Field::INVALID);
+        assert_eq!(Field::from(64u8), Field::INVALID);
+
+        assert_eq!(
+            (Field::H1.file(), Field::H1.rank()),
+            (File::FileH, Rank::Rank1)
+        );
+        assert_eq!(
+            (Field::A8.file(), Field::A8.rank()),
+            (File::FileA, Rank::Rank8)
+        );
+        assert_eq!(Field::INVALID.file(), File::INVALID);
+        assert_eq!(Field::INVALID.rank(), Rank::INVALID);
+        let point = Point::from(Field::E2);
+        assert_eq!((point.file, point.rank), (4, 1));
+    }
+
+    #[test]
+    fn set_and_unset() {
+        let mut board = Bitboard::default();
+        board.set(Field::E4);
+        board.set(Field::D5);
+        assert!(board.is_set(Field::E4) && board.is_set(Field::D5));
+        assert_eq!(board.count(), 2);
+        board.set(Field::INVALID);
+        assert_eq!(board.count(), 2);
+        assert!(!board.is_set(Field::INVALID));
+        board.unset(Field::E4);
+        board.unset(Field::INVALID);
+        assert_eq!(board, Bitboard::from(Field::D5));
+    }
+
+    #[test]
+    fn field_iterators() {
+        let mut board = Bitboard::default();
+        board.set(Field::A1);
+        board.set(Field::H1);
+        board.set(Field::H8);
+        let set: Vec<Field> = board.set_fields_iter().collect();
+        assert_eq!(set, [Field::A1, Field::H1, Field::H8]);
+        let unset: Vec<Field> = board.unset_fields_iter().collect();
+        assert_eq!(unset.len(), 61);
+        assert_eq!(unset.first(), Some(&Field::B1));
+        assert_eq!(unset.last(), Some(&Field::G8));
+        assert_eq!(Bitboard::default().unset_fields_iter().count(), 64);
+        assert_eq!(Bitboard::new(!0).set_fields_iter().count(), 64);
+    }
+
+    #[test]
+    fn display() {
+        let mut board = Bitboard::default();
+        board.set(Field::A1);
+        board.set(Field::H8);
+        board.set(Field::E4);
+        assert_eq!(
+            board.to_string(),
+            "8 .......x\n\
+             7 ........\n\
+             6 ........\n\
+             5 ........\n\
+             4 ....x...\n\
+             3 ........\n\
+             2 ........\n\
+             1 x.......\n  \
+             ABCDEFGH\n"
+        );
+    }
+
+    #[test]
+    fn shift_drops_fields_leaving_board() {
+        const FILE_A: Bitboard = Bitboard::new(Bitboard::FILE_A);
+        const FILE_B: Bitboard = FILE_A.shift(Direction::E);
+        assert_eq!(FILE_B, Bitboard::file(File::FileB));
+        assert!(FILE_A.shift(Direction::W).is_empty());
+        assert!(FILE_B.shift(Direction::WW).is_empty());
+        assert!(Bitboard::file(File::FileH).shift(Direction::E).is_empty());
+        assert!(Bitboard::file(File::FileG).shift(Direction::SEE).is_empty());
+
+        let field = |field| Bitboard::from(field);
+        assert_eq!(field(Field::H4).shift(Direction::W), field(Field::G4));
+        assert!(field(Field::H4).shift(Direction::NE).is_empty());
+        assert!(field(Field::A1).shift(Direction::S).is_empty());
+        assert!(field(Field::H8).shift(Direction::N).is_empty());
+        assert_eq!(field(Field::C1).shift(Direction::NNWW), field(Field::A3));
+        assert_eq!(field(Field::E4).shift(Direction::H), field(Field::E4));
+        assert_eq!(
+            Bitboard::new(!0).shift(Direction::N),
+            !Bitboard::rank(Rank::Rank1)
+        );
+    }
+
+    #[test]
+    fn mv() {
+        assert_eq!(Field::E2 + Direction::N, Field::E3);
+        assert_eq!(Field::A1 + Direction::NNEE, Field::C3);
+        assert_eq!(Field::B1 + Direction::NNW, Field::A3);
+        assert_eq!(Field::E4 + Direction::H, Field::E4);
+        assert_eq!(Field::A1 + Direction::W, Field::INVALID);
+        assert_eq!(Field::H8 + Direction::NE, Field::INVALID);
+        assert_eq!(Field::G1 + Direction::EE, Field::INVALID);
+        assert_eq!(Field::B2 + Direction::SS, Field::INVALID);
+        assert_eq!(Field::INVALID + Direction::N, Field::INVALID);
+    }
+
+    #[test]
+    fn distance() {
+        const DIAGONAL: u8 = Field::A1.distance(Field::H8);
+        assert_eq!(DIAGONAL, 7);
+        assert_eq!(Field::A1.distance(Field::A1), 0);
+        assert_eq!(Field::A1.distance(Field::H1), 7);
+        assert_eq!(Field::H1.distance(Field::A8), 7);
+        assert_eq!(Field::B2.distance(Field::C4), 2);
+        assert_eq!(Field::C4.distance(Field::B2), 2);
+    }
+
+    #[test]
+    fn relative() {
+        const E7: Field = Field::E2.relative(Color::Black);
+        assert_eq!(E7, Field::E7);
+        assert_eq!(Field::A1.relative(Color::Black), Field::A8);
+        assert_eq!(Field::H8.relative(Color::Black), Field::H1);
+        assert_eq!(Field::H8.relative(Color::White), Field::H8);
+        assert_eq!(Field::INVALID.relative(Color::Black), Field::INVALID);
+        assert_eq!(Rank::Rank1.relative(Color::Black), Rank::Rank8);
+        assert_eq!(Rank::Rank5.relative(Color::Black), Rank::Rank4);
+        assert_eq!(Rank::Rank5.relative(Color::White), Rank::Rank5);
+        assert_eq!(Rank::INVALID.relative(Color::Black), Rank::INVALID);
+    }
 }
@@ -0,0 +1,519 @@
+//! Efficiently updatable neural network evaluation.
+//!
+//! The network starts with the feature transformer, which turns the pieces on the board
+//! into the first hidden layer, computed separately from the point of view of each side.
+//! Both halves, the side to move first, are followed by the dense layers ending with a single output.
+//! Which inputs there are is decided by the [FeatureSet] given in the network file.
+//!
+//! All the arithmetic is done on integers. The transformer weights are 16-bit, and its outputs
+//! are clipped to `0..=127`. The dense layers have 8-bit weights and 32-bit biases, and their sums
+//! are shifted right by six bits before clipping. The last layer isn't clipped, and its output
+//! is divided by sixteen to get the centipawns.
+//!
+//! With the `embedded-network` feature, the network file given in the `RSCHESS_NETWORK`
+//! environment variable at build time is embedded in the binary, see `Network::embedded`.
+//! The path is either absolute or relative to the crate root, the directory with `Cargo.toml`.
+//! Without the variable nothing is embedded, and the evaluation goes without a network.
+//!
+
+use crate::eval::Score;
+use crate::{Color, Field, Piece, Position};
+use alloc::vec::Vec;
+use core::fmt::Display;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::path::Path;
+use strum::IntoEnumIterator;
+
+mod accumulator;
+mod features;
+
+pub use accumulator::{Accumulator, AccumulatorStack};
+pub use features::{feature_set, FeatureSet, HalfKa, HalfKp, PieceSquare, FEATURE_SETS};
+
+/// Bytes starting every network file.
+const MAGIC: [u8; 4] = *b"RSNN";
+
+/// Version of the network file format.
+const VERSION: u32 = 2;
+
+/// The network embedded in the binary.
+#[cfg(feature = "embedded-network")]
+static EMBEDDED: &[u8] = include_bytes!(env!("RSCHESS_EMBEDDED_NETWORK"));
+
+/// Upper limit of the clipped activations.
+const ACTIVATION_MAX: i32 = 127;
+
+/// Shift scaling the dense layer sums back to the activation range.
+const WEIGHT_SHIFT: u32 = 6;
+
+/// Divisor scaling the network output to the centipawns.
+const OUTPUT_SCALE: i32 = 16;
+
+/// Largest number of the outputs of a dense layer, so the activations fit in fixed-size buffers.
+pub const MAX_LAYER_OUTPUTS: usize = 256;
+
+/// Reasons why a network can't be loaded.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum NetworkError {
+    /// The data ends before all the weights are read.
+    Truncated,
+    /// There is more data after all the weights.
+    TrailingData,
+    /// The layer sizes don't fit together, or a dense layer has more than [MAX_LAYER_OUTPUTS].
+    InvalidLayers,
+    /// The feature set with the identifier from the header isn't known.
+    UnknownFeatureSet(u32),
+    /// The data doesn't start with the network file magic bytes.
+    InvalidMagic,
+    /// The file format version isn't supported.
+    UnsupportedVersion(u32),
+    /// The architecture hash in the header doesn't match the layer sizes.
+    ArchitectureMismatch { expected: u32, found: u32 },
+    /// The network file can't be read.
+    #[cfg(feature = "std")]
+    Io(io::Error),
+}
+
+impl Display for NetworkError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            NetworkError::Truncated => write!(f, "network data is truncated"),
+            NetworkError::TrailingData => write!(f, "unexpected data after the network weights"),
+            NetworkError::InvalidLayers => write!(f, "network layer sizes don't fit together"),
+            NetworkError::UnknownFeatureSet(id) => write!(f, "unknown network feature set {id}"),
+            NetworkError::InvalidMagic => write!(f, "not a network file"),
+            NetworkError::UnsupportedVersion(version) => {
+                write!(f, "unsupported network file version {version}, expected {VERSION}")
+            }
+            NetworkError::ArchitectureMismatch { expected, found } => write!(
+                f,
+                "network architecture hash {found:08x} doesn't match the layers, expected {expected:08x}"
+            ),
+            #[cfg(feature = "std")]
+            NetworkError::Io(error) => write!(f, "can't read network file: {error}"),
+        }
+    }
+}
+
+impl core::error::Error for NetworkError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            NetworkError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Fully connected layer after the feature transformer.
+#[derive(Clone, Debug, PartialEq)]
+struct Dense {
+    inputs: usize,
+    outputs: usize,
+    biases: Vec<i32>,
+    /// Weights of each output in turn.
+    weights: Vec<i8>,
+}
+
+impl Dense {
+    /// Computes the layer outputs into the `output`, without the scaling and clipping.
+    fn propagate(&self, input: impl Iterator<Item = i32> + Clone, output: &mut [i32]) {
+        let outputs = self.weights.chunks(self.inputs).zip(&self.biases);
+        for (output, (weights, &bias)) in output.iter_mut().zip(outputs) {
+            *output = weights
+                .iter()
+                .zip(input.clone())
+                .fold(bias, |sum, (&weight, value)| {
+                    sum.wrapping_add((weight as i32).wrapping_mul(value))
+                });
+        }
+    }
+}
+
+/// Quantized network weights.
+///
+/// The network file starts with the `RSNN` magic bytes, followed by little-endian numbers:
+/// the format version, the architecture hash, the feature set identifier, the size of the transformer
+/// output, and the number of the dense layers followed by the number of the outputs of each of them.
+/// Then go the transformer biases and weights of each input in turn, and the biases and weights
+/// of each output in turn for all the dense layers.
+#[derive(Clone, Debug)]
+pub struct Network {
+    features: &'static dyn FeatureSet,
+    transformer_biases: Vec<i16>,
+    /// Weights of each input in turn.
+    transformer_weights: Vec<i16>,
+    layers: Vec<Dense>,
+}
+
+impl Network {
+    /// Reads the network from the file contents.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, NetworkError> {
+        let mut reader = Reader { bytes };
+        if reader.take()? != MAGIC {
+            return Err(NetworkError::InvalidMagic);
+        }
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(NetworkError::UnsupportedVersion(version));
+        }
+        let found = reader.u32()?;
+        let id = reader.u32()?;
+        let hidden = reader.u32()? as usize;
+        let count = reader.u32()? as usize;
+        let sizes = (0..count)
+            .map(|_| reader.u32().map(|size| size as usize))
+            .collect::<Result<Vec<_>, _>>()?;
+        let features = feature_set(id).ok_or(NetworkError::UnknownFeatureSet(id))?;
+        let inputs = features.inputs();
+        let expected = architecture_hash(features, hidden, &sizes);
+        if found != expected {
+            return Err(NetworkError::ArchitectureMismatch { expected, found });
+        }
+        if hidden == 0
+            || sizes.last() != Some(&1)
+            || sizes.contains(&0)
+            || sizes.iter().any(|&size| size > MAX_LAYER_OUTPUTS)
+        {
+            return Err(NetworkError::InvalidLayers);
+        }
+
+        let transformer_biases = reader.values(hidden, Reader::i16)?;
+        let transformer_weights = reader.values(
+            inputs.checked_mul(hidden).ok_or(NetworkError::Truncated)?,
+            Reader::i16,
+        )?;
+        let mut layers = Vec::with_capacity(count);
+        let mut layer_inputs = 2 * hidden;
+        for outputs in sizes {
+            layers.push(Dense {
+                inputs: layer_inputs,
+                outputs,
+                biases: reader.values(outputs, Reader::i32)?,
+                weights: reader.values(
+                    layer_inputs
+                        .checked_mul(outputs)
+                        .ok_or(NetworkError::Truncated)?,
+                    Reader::i8,
+                )?,
+            });
+            layer_inputs = outputs;
+        }
+        if !reader.bytes.is_empty() {
+            return Err(NetworkError::TrailingData);
+        }
+        Ok(Self {
+            features,
+            transformer_biases,
+            transformer_weights,
+            layers,
+        })
+    }
+
+    /// Reads the network from the file at the `path`.
+    #[cfg(feature = "std")]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, NetworkError> {
+        let bytes = std::fs::read(path).map_err(NetworkError::Io)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Returns the network embedded in the binary.
+    ///
+    /// Fails when the crate was built without the `RSCHESS_NETWORK` variable, as nothing is embedded then.
+    #[cfg(feature = "embedded-network")]
+    pub fn embedded() -> Result<Self, NetworkError> {
+        Self::from_bytes(EMBEDDED)
+    }
+
+    /// Writes the network in the format read by [Network::from_bytes].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let sizes: Vec<usize> = self.layers.iter().map(|layer| layer.outputs).collect();
+        let mut bytes = MAGIC.to_vec();
+        let header = [
+            VERSION,
+            architecture_hash(self.features, self.hidden(), &sizes),
+            self.features.id(),
+            self.hidden() as u32,
+            sizes.len() as u32,
+        ];
+        for value in header
+            .into_iter()
+            .chain(sizes.iter().map(|&size| size as u32))
+        {
+            bytes.extend(value.to_le_bytes());
+        }
+        for value in self
+            .transformer_biases
+            .iter()
+            .chain(&self.transformer_weights)
+        {
+            bytes.extend(value.to_le_bytes());
+        }
+        for layer in &self.layers {
+            for bias in &layer.biases {
+                bytes.extend(bias.to_le_bytes());
+            }
+            for weight in &layer.weights {
+                bytes.extend(weight.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    /// Returns the input features of the network.
+    pub fn features(&self) -> &'static dyn FeatureSet {
+        self.features
+    }
+
+    /// Returns the size of the feature transformer output for one side.
+    pub fn hidden(&self) -> usize {
+        self.transformer_biases.len()
+    }
+
+    /// Evaluates the position from the point of view of the side to move.
+    ///
+    /// This computes the feature transformer from scratch, use an [Accumulator] for evaluating similar positions.
+    pub fn evaluate(&self, position: &Position) -> Score {
+        Accumulator::new(self, position).evaluate(self)
+    }
+
+    /// Computes the feature transformer output from the `perspective` of one side into the `values`,
+    /// reusing their memory.
+    fn transform(&self, position: &Position, perspective: Color, values: &mut Vec<i16>) {
+        values.clear();
+        values.extend_from_slice(&self.transformer_biases);
+        let king = king_field(position, perspective);
+        for color in Color::iter() {
+            for piece in Piece::iter() {
+                for field in position.pieces(color, piece).set_fields_iter() {
+                    let feature = self.features.index(perspective, king, color, piece, field);
+                    self.add_feature(values, feature);
+                }
+            }
+        }
+    }
+
+    /// Adds weights of the `feature` to the transformer output.
+    fn add_feature(&self, values: &mut [i16], feature: Option<usize>) {
+        let Some(feature) = feature else {
+            return;
+        };
+        let weights = &self.transformer_weights[feature * self.hidden()..];
+        for (value, &weight) in values.iter_mut().zip(weights) {
+            *value = value.wrapping_add(weight);
+        }
+    }
+
+    /// Subtracts weights of the `feature` from the transformer output.
+    fn remove_feature(&self, values: &mut [i16], feature: Option<usize>) {
+        let Some(feature) = feature else {
+            return;
+        };
+        let weights = &self.transformer_weights[feature * self.hidden()..];
+        for (value, &weight) in values.iter_mut().zip(weights) {
+            *value = value.wrapping_sub(weight);
+        }
+    }
+
+    /// Runs the dense layers on the transformer outputs of the side to move and the other side.
+    ///
+    /// The outputs of each layer go to one of two buffers on the stack, taking turns,
+    /// so the evaluation doesn't allocate.
+    fn propagate(&self, us: &[i16], them: &[i16]) -> Score {
+        let mut buffers = [[0; MAX_LAYER_OUTPUTS]; 2];
+        let (first, rest) = self.layers.split_first().unwrap();
+        let input = us
+            .iter()
+            .chain(them)
+            .map(|&value| (value as i32).clamp(0, ACTIVATION_MAX));
+        first.propagate(input, &mut buffers[0][..first.outputs]);
+        let mut size = first.outputs;
+        for (index, layer) in rest.iter().enumerate() {
+            let [even, odd] = &mut buffers;
+            let (input, output) = match index % 2 {
+                0 => (even, odd),
+                _ => (odd, even),
+            };
+            let input = input[..size]
+                .iter()
+                .map(|&sum| (sum >> WEIGHT_SHIFT).clamp(0, ACTIVATION_MAX));
+            layer.propagate(input, &mut output[..layer.outputs]);
+            size = layer.outputs;
+        }
+        buffers[rest.len() % 2][0] / OUTPUT_SCALE
+    }
+}
+
+impl PartialEq for Network {
+    fn eq(&self, other: &Self) -> bool {
+        self.features.id() == other.features.id()
+            && self.transformer_biases == other.transformer_biases
+            && self.transformer_weights == other.transformer_weights
+            && self.layers == other.layers
+    }
+}
+
+/// Hashes the feature set and the layer sizes with FNV-1a, to catch the files with a corrupted or mismatched header.
+fn architecture_hash(features: &dyn FeatureSet, hidden: usize, sizes: &[usize]) -> u32 {
+    [features.id() as usize, features.inputs(), hidden]
+        .iter()
+        .chain(sizes)
+        .flat_map(|&size| (size as u32).to_le_bytes())
+        .fold(0x811C_9DC5, |hash, byte| {
+            (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+        })
+}
+
+/// Returns the field of the king of the `color`, or A1 for the boards without the king.
+fn king_field(position: &Position, color: Color) -> Field {
+    position
+        .pieces(color, Piece::King)
+        .set_fields_iter()
+        .next()
+        .unwrap_or(Field::A1)
+}
+
+/// Reads the little-endian numbers from the start of the data.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    /// Takes the next `N` bytes.
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], NetworkError> {
+        let (head, rest) = self
+            .bytes
+            .split_first_chunk()
+            .ok_or(NetworkError::Truncated)?;
+        self.bytes = rest;
+        Ok(*head)
+    }
+
+    fn u32(&mut self) -> Result<u32, NetworkError> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    fn i32(&mut self) -> Result<i32, NetworkError> {
+        self.take().map(i32::from_le_bytes)
+    }
+
+    fn i16(&mut self) -> Result<i16, NetworkError> {
+        self.take().map(i16::from_le_bytes)
+    }
+
+    fn i8(&mut self) -> Result<i8, NetworkError> {
+        self.take().map(i8::from_le_bytes)
+    }
+
+    /// Reads `count` numbers with the `read` function.
+    fn values<T>(
+        &mut self,
+        count: usize,
+        read: fn(&mut Self) -> Result<T, NetworkError>,
+    ) -> Result<Vec<T>, NetworkError> {
+        if count > self.bytes.len() {
+            return Err(NetworkError::Truncated);
+        }
+        (0..count).map(|_| read(self)).collect()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::eval::verify::random_positions;
+
+    /// Creates the network with pseudo-random weights, small enough not to saturate the layers.
+    pub(crate) fn random_network(
+        seed: u64,
+        features: &'static dyn FeatureSet,
+        hidden: usize,
+        sizes: &[usize],
+    ) -> Network {
+        let mut state = seed;
+        let mut next = |range: i64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((state >> 33) as i64 % (2 * range + 1)) - range
+        };
+        let mut layers = Vec::new();
+        let mut inputs = 2 * hidden;
+        for &outputs in sizes {
+            layers.push(Dense {
+                inputs,
+                outputs,
+                biases: (0..outputs).map(|_| next(100) as i32).collect(),
+                weights: (0..inputs * outputs).map(|_| next(50) as i8).collect(),
+            });
+            inputs = outputs;
+        }
+        Network {
+            features,
+            transformer_biases: (0..hidden).map(|_| next(32) as i16 + 16).collect(),
+            transformer_weights: (0..features.inputs() * hidden)
+                .map(|_| next(20) as i16)
+                .collect(),
+            layers,
+        }
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        for (features, sizes) in [
+            (&PieceSquare as &'static dyn FeatureSet, &[1][..]),
+            (&HalfKp, &[8, 4, 1]),
+            (&HalfKa, &[4, 1]),
+        ] {
+            let network = random_network(3, features, 8, sizes);
+            let bytes = network.to_bytes();
+            assert_eq!(Network::from_bytes(&bytes).unwrap(), network);
+            assert_eq!(Network::from_bytes(&bytes).unwrap().to_bytes(), bytes);
+        }
+    }
+
+    #[test]
+    fn invalid_bytes() {
+        let bytes = random_network(4, &PieceSquare, 8, &[4, 1]).to_bytes();
+        let error = |bytes: &[u8]| Network::from_bytes(bytes).unwrap_err();
+        assert!(matches!(
+            error(&bytes[..bytes.len() - 1]),
+            NetworkError::Truncated
+        ));
+        assert!(matches!(
+            error(&[&bytes[..], &[0]].concat()),
+            NetworkError::TrailingData
+        ));
+        assert!(matches!(error(b"RSNX"), NetworkError::InvalidMagic));
+        let large = random_network(4, &PieceSquare, 8, &[MAX_LAYER_OUTPUTS + 1, 1]);
+        assert!(matches!(
+            error(&large.to_bytes()),
+            NetworkError::InvalidLayers
+        ));
+        let mut other = bytes.clone();
+        other[4] = 9;
+        assert!(matches!(error(&other), NetworkError::UnsupportedVersion(9)));
+        let mut other = bytes.clone();
+        other[8] ^= 1;
+        assert!(matches!(
+            error(&other),
+            NetworkError::ArchitectureMismatch { .. }
+        ));
+    }
+
+    #[test]
+    fn evaluates_color_symmetric() {
+        let network = random_network(5, &HalfKa, 16, &[8, 1]);
+        for position in random_positions(11, 200) {
+            assert_eq!(
+                network.evaluate(&position),
+                network.evaluate(&position.mirrored()),
+                "{}",
+                position.to_fen()
+            );
+        }
+    }
+}
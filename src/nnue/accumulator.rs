@@ -0,0 +1,240 @@
+//! Feature transformer outputs updated incrementally.
+//!
+
+use super::{king_field, Network};
+use crate::eval::Score;
+use crate::{Bitboard, Color, Piece, Position};
+use alloc::{vec, vec::Vec};
+use strum::IntoEnumIterator;
+
+/// Feature transformer outputs for both sides, kept up to date with the position.
+///
+/// Moving a piece changes just a few features, so updating the outputs for them
+/// is much faster than computing them from scratch.
+#[derive(Debug, PartialEq)]
+pub struct Accumulator {
+    values: [Vec<i16>; 2],
+    position: Position,
+}
+
+impl Clone for Accumulator {
+    fn clone(&self) -> Self {
+        Self {
+            values: self.values.clone(),
+            position: self.position,
+        }
+    }
+
+    /// Copies the outputs into the memory of this accumulator.
+    fn clone_from(&mut self, source: &Self) {
+        self.values[0].clone_from(&source.values[0]);
+        self.values[1].clone_from(&source.values[1]);
+        self.position = source.position;
+    }
+}
+
+impl Accumulator {
+    /// Computes the outputs for the position from scratch.
+    pub fn new(network: &Network, position: &Position) -> Self {
+        let mut accumulator = Self {
+            values: [
+                Vec::with_capacity(network.hidden()),
+                Vec::with_capacity(network.hidden()),
+            ],
+            position: *position,
+        };
+        accumulator.refresh(network, position);
+        accumulator
+    }
+
+    /// Computes the outputs for the position from scratch, reusing the memory.
+    fn refresh(&mut self, network: &Network, position: &Position) {
+        for perspective in Color::iter() {
+            network.transform(
+                position,
+                perspective,
+                &mut self.values[perspective as usize],
+            );
+        }
+        self.position = *position;
+    }
+
+    /// Returns the position the outputs are computed for.
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// Updates the outputs for the new position, adding and removing only the pieces which differ.
+    ///
+    /// When there are more differences than pieces on the board, e.g. for unrelated positions,
+    /// the outputs are computed from scratch instead. The same goes for the outputs of a side
+    /// whose king has moved, if the features depend on the king field.
+    pub fn update(&mut self, network: &Network, position: &Position) {
+        let mut removed = [[Bitboard::default(); 6]; 2];
+        let mut added = [[Bitboard::default(); 6]; 2];
+        let mut changes = 0;
+        for color in Color::iter() {
+            for piece in Piece::iter() {
+                let old = self.position.pieces(color, piece);
+                let new = position.pieces(color, piece);
+                removed[color as usize][piece as usize] = old & !new;
+                added[color as usize][piece as usize] = new & !old;
+                changes += (old ^ new).count();
+            }
+        }
+        if changes > position.occupied().count() {
+            self.refresh(network, position);
+            return;
+        }
+
+        let features = network.features();
+        for perspective in Color::iter() {
+            let values = &mut self.values[perspective as usize];
+            let king = king_field(position, perspective);
+            if features.king_relative() && king != king_field(&self.position, perspective) {
+                network.transform(position, perspective, values);
+                continue;
+            }
+            for color in Color::iter() {
+                for piece in Piece::iter() {
+                    for field in removed[color as usize][piece as usize].set_fields_iter() {
+                        let feature = features.index(perspective, king, color, piece, field);
+                        network.remove_feature(values, feature);
+                    }
+                    for field in added[color as usize][piece as usize].set_fields_iter() {
+                        let feature = features.index(perspective, king, color, piece, field);
+                        network.add_feature(values, feature);
+                    }
+                }
+            }
+        }
+        self.position = *position;
+    }
+
+    /// Evaluates the position from the point of view of the side to move.
+    pub fn evaluate(&self, network: &Network) -> Score {
+        let side = self.position.side_to_move();
+        network.propagate(&self.values[side as usize], &self.values[!side as usize])
+    }
+}
+
+/// Accumulators following the moves made and taken back during the search.
+///
+/// The accumulators above the current one are kept after the pops, so the next pushes
+/// copy the outputs into their memory instead of allocating.
+#[derive(Clone, Debug)]
+pub struct AccumulatorStack {
+    stack: Vec<Accumulator>,
+    /// Number of the accumulators in use.
+    len: usize,
+}
+
+impl AccumulatorStack {
+    /// Creates the stack with the accumulator of the root position.
+    pub fn new(network: &Network, position: &Position) -> Self {
+        Self {
+            stack: vec![Accumulator::new(network, position)],
+            len: 1,
+        }
+    }
+
+    /// Adds the accumulator of the position after a move, updated from the current one.
+    pub fn push(&mut self, network: &Network, position: &Position) {
+        if self.len == self.stack.len() {
+            self.stack.push(self.top().clone());
+        } else {
+            let (below, above) = self.stack.split_at_mut(self.len);
+            above[0].clone_from(&below[self.len - 1]);
+        }
+        self.stack[self.len].update(network, position);
+        self.len += 1;
+    }
+
+    /// Goes back to the accumulator from before the last push. The root one is never removed.
+    pub fn pop(&mut self) {
+        if self.len > 1 {
+            self.len -= 1;
+        }
+    }
+
+    /// Returns the accumulator of the current position.
+    pub fn top(&self) -> &Accumulator {
+        &self.stack[self.len - 1]
+    }
+}
+
+/// Compares just the accumulators in use.
+impl PartialEq for AccumulatorStack {
+    fn eq(&self, other: &Self) -> bool {
+        self.stack[..self.len] == other.stack[..other.len]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::verify::random_positions;
+    use crate::nnue::tests::random_network;
+    use crate::nnue::{FeatureSet, HalfKa, HalfKp, PieceSquare};
+    use crate::Field;
+
+    /// Moves the piece from the first occupied field of the `mask` to the first empty field after it.
+    fn moved(position: &Position, mask: Bitboard) -> Option<Position> {
+        let from = (position.occupied() & mask).set_fields_iter().next()?;
+        let to = (0..64u8)
+            .map(|index| Field::from((from as u8 + index) % 64))
+            .find(|&field| position.piece_at(field).is_none())?;
+        let (color, piece) = position.piece_at(from)?;
+        let mut position = *position;
+        position.remove(from);
+        position.put(color, piece, to);
+        Some(position)
+    }
+
+    #[test]
+    fn incremental_matches_from_scratch() {
+        for features in [&PieceSquare as &'static dyn FeatureSet, &HalfKp, &HalfKa] {
+            let network = random_network(9, features, 16, &[4, 1]);
+            let positions = random_positions(13, 100);
+            let mut stack = AccumulatorStack::new(&network, &positions[0]);
+            let mut accumulator = Accumulator::new(&network, &positions[0]);
+            for position in &positions {
+                let kings = position.pieces(Color::White, Piece::King);
+                let next: Vec<Position> = [
+                    Some(*position),
+                    moved(position, !kings),
+                    moved(position, kings),
+                ]
+                .into_iter()
+                .flatten()
+                .collect();
+                for next in &next {
+                    let scratch = Accumulator::new(&network, next);
+                    accumulator.update(&network, next);
+                    assert_eq!(accumulator, scratch);
+                    stack.push(&network, next);
+                    assert_eq!(stack.top(), &scratch);
+                    assert_eq!(stack.top().evaluate(&network), network.evaluate(next));
+                }
+                // Keep the position itself, so the stack grows over the loop.
+                for _ in 1..next.len() {
+                    stack.pop();
+                }
+                assert_eq!(stack.top().position(), position);
+            }
+        }
+    }
+
+    #[test]
+    fn root_is_never_popped() {
+        let network = random_network(10, &PieceSquare, 8, &[1]);
+        let positions = random_positions(14, 3);
+        let mut stack = AccumulatorStack::new(&network, &positions[0]);
+        stack.push(&network, &positions[1]);
+        stack.pop();
+        stack.pop();
+        assert_eq!(stack, AccumulatorStack::new(&network, &positions[0]));
+        stack.push(&network, &positions[2]);
+        assert_eq!(stack.top(), &Accumulator::new(&network, &positions[2]));
+    }
+}
@@ -0,0 +1,196 @@
+//! Input features of the network.
+//!
+
+use crate::{Color, Field, Piece};
+use core::fmt::Debug;
+
+/// Way of turning the pieces on the board into the network inputs.
+///
+/// Each piece is an input as seen from the point of view of each side, called the perspective.
+/// The board is flipped for black, so both sides see their own pieces first and on their own half of the board.
+pub trait FeatureSet: Debug + Send + Sync {
+    /// Identifier of the set written in the network file header.
+    fn id(&self) -> u32;
+
+    /// Returns the number of the inputs.
+    fn inputs(&self) -> usize;
+
+    /// Tells if the inputs of the perspective depend on the field of its king,
+    /// so all of them change when the king moves.
+    fn king_relative(&self) -> bool;
+
+    /// Returns the input index of the `piece` of the `color` on the `field`, seen from the `perspective`
+    /// with its king on the `king` field, or `None` if the piece isn't an input of this set.
+    fn index(
+        &self,
+        perspective: Color,
+        king: Field,
+        color: Color,
+        piece: Piece,
+        field: Field,
+    ) -> Option<usize>;
+}
+
+/// Pieces on the fields, without the king fields: 768 inputs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PieceSquare;
+
+impl FeatureSet for PieceSquare {
+    fn id(&self) -> u32 {
+        1
+    }
+
+    fn inputs(&self) -> usize {
+        2 * 6 * 64
+    }
+
+    fn king_relative(&self) -> bool {
+        false
+    }
+
+    fn index(
+        &self,
+        perspective: Color,
+        _king: Field,
+        color: Color,
+        piece: Piece,
+        field: Field,
+    ) -> Option<usize> {
+        Some(piece_index(perspective, color, piece) * 64 + field.relative(perspective) as usize)
+    }
+}
+
+/// Pieces other than kings on the fields, for each field of our king: 40960 inputs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HalfKp;
+
+impl FeatureSet for HalfKp {
+    fn id(&self) -> u32 {
+        2
+    }
+
+    fn inputs(&self) -> usize {
+        64 * 2 * 5 * 64
+    }
+
+    fn king_relative(&self) -> bool {
+        true
+    }
+
+    fn index(
+        &self,
+        perspective: Color,
+        king: Field,
+        color: Color,
+        piece: Piece,
+        field: Field,
+    ) -> Option<usize> {
+        if piece == Piece::King {
+            return None;
+        }
+        let side = (color != perspective) as usize;
+        let piece = side * 5 + piece as usize;
+        let king = king.relative(perspective) as usize;
+        Some((king * 2 * 5 + piece) * 64 + field.relative(perspective) as usize)
+    }
+}
+
+/// All the pieces, including both kings, on the fields, for each field of our king: 49152 inputs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HalfKa;
+
+impl FeatureSet for HalfKa {
+    fn id(&self) -> u32 {
+        3
+    }
+
+    fn inputs(&self) -> usize {
+        64 * 2 * 6 * 64
+    }
+
+    fn king_relative(&self) -> bool {
+        true
+    }
+
+    fn index(
+        &self,
+        perspective: Color,
+        king: Field,
+        color: Color,
+        piece: Piece,
+        field: Field,
+    ) -> Option<usize> {
+        let king = king.relative(perspective) as usize;
+        let piece = piece_index(perspective, color, piece);
+        Some((king * 2 * 6 + piece) * 64 + field.relative(perspective) as usize)
+    }
+}
+
+/// All the known feature sets.
+pub static FEATURE_SETS: [&dyn FeatureSet; 3] = [&PieceSquare, &HalfKp, &HalfKa];
+
+/// Returns the feature set with the `id`, if it's known.
+pub fn feature_set(id: u32) -> Option<&'static dyn FeatureSet> {
+    FEATURE_SETS.iter().copied().find(|set| set.id() == id)
+}
+
+/// Returns the index of the piece kind, with our pieces first.
+fn piece_index(perspective: Color, color: Color, piece: Piece) -> usize {
+    (color != perspective) as usize * 6 + piece as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn indexes_are_distinct_and_in_range() {
+        for set in FEATURE_SETS {
+            assert_eq!(
+                feature_set(set.id()).map(|found| found.id()),
+                Some(set.id())
+            );
+            for king in [Field::A1, Field::E1, Field::D5, Field::H8] {
+                let mut seen = vec![false; set.inputs()];
+                for color in Color::iter() {
+                    for piece in Piece::iter() {
+                        for field in (0..64u8).map(Field::from) {
+                            let Some(index) = set.index(Color::White, king, color, piece, field)
+                            else {
+                                continue;
+                            };
+                            assert!(!seen[index], "{set:?} repeats {index}");
+                            seen[index] = true;
+                        }
+                    }
+                }
+            }
+        }
+        assert!(feature_set(0).is_none());
+    }
+
+    #[test]
+    fn perspectives_are_mirrored() {
+        for set in FEATURE_SETS {
+            for color in Color::iter() {
+                for piece in Piece::iter() {
+                    for field in (0..64u8).map(Field::from) {
+                        assert_eq!(
+                            set.index(Color::White, Field::E1, color, piece, field),
+                            set.index(
+                                Color::Black,
+                                Field::E8,
+                                !color,
+                                piece,
+                                field.relative(Color::Black)
+                            ),
+                            "{set:?}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,368 @@
+//! Engine options, as set with the UCI `setoption` command.
+//!
+//! The components of the engine register their options in [Options] and read them back
+//! when they change. The options are listed and changed in the UCI format, with the names
+//! compared case-insensitively.
+//!
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+/// Type of the option, with its default value and limits.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OptionKind {
+    /// On or off.
+    Check { default: bool },
+    /// Whole number in the range.
+    Spin { default: i64, min: i64, max: i64 },
+    /// One of the listed strings.
+    Combo {
+        default: String,
+        choices: Vec<String>,
+    },
+    /// Action without a value, like clearing the hash.
+    Button,
+    /// Any text, like a file path.
+    String { default: String },
+}
+
+impl OptionKind {
+    /// Returns the default value of the option.
+    fn default_value(&self) -> OptionValue {
+        match self {
+            OptionKind::Check { default } => OptionValue::Check(*default),
+            OptionKind::Spin { default, .. } => OptionValue::Spin(*default),
+            OptionKind::Combo { default, .. } => OptionValue::String(default.clone()),
+            OptionKind::Button => OptionValue::Button,
+            OptionKind::String { default } => OptionValue::String(default.clone()),
+        }
+    }
+}
+
+/// Current value of an option.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OptionValue {
+    Check(bool),
+    Spin(i64),
+    /// Value of the combo and string options.
+    String(String),
+    Button,
+}
+
+/// Reasons why an option can't be set.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OptionError {
+    /// There is no option with the name.
+    UnknownOption(String),
+    /// The value doesn't fit the option type or limits.
+    InvalidValue { name: String, value: String },
+    /// The `setoption` command doesn't have the option name.
+    Malformed(String),
+}
+
+impl Display for OptionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            OptionError::UnknownOption(name) => write!(f, "unknown option '{name}'"),
+            OptionError::InvalidValue { name, value } => {
+                write!(f, "invalid value '{value}' of option '{name}'")
+            }
+            OptionError::Malformed(command) => write!(f, "malformed setoption command '{command}'"),
+        }
+    }
+}
+
+impl core::error::Error for OptionError {}
+
+/// Registered option with its current value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EngineOption {
+    name: String,
+    kind: OptionKind,
+    value: OptionValue,
+    changed: bool,
+}
+
+impl EngineOption {
+    /// Returns the name of the option.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the type of the option.
+    pub fn kind(&self) -> &OptionKind {
+        &self.kind
+    }
+
+    /// Returns the current value of the option.
+    pub fn value(&self) -> &OptionValue {
+        &self.value
+    }
+}
+
+/// Formats the option as the line listing it in the reply to the `uci` command.
+impl Display for EngineOption {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "option name {} type ", self.name)?;
+        match &self.kind {
+            OptionKind::Check { default } => write!(f, "check default {default}"),
+            OptionKind::Spin { default, min, max } => {
+                write!(f, "spin default {default} min {min} max {max}")
+            }
+            OptionKind::Combo { default, choices } => {
+                write!(f, "combo default {default}")?;
+                choices
+                    .iter()
+                    .try_for_each(|choice| write!(f, " var {choice}"))
+            }
+            OptionKind::Button => write!(f, "button"),
+            OptionKind::String { default } if default.is_empty() => {
+                write!(f, "string default <empty>")
+            }
+            OptionKind::String { default } => write!(f, "string default {default}"),
+        }
+    }
+}
+
+/// Registry of all the engine options, kept in the order of registration.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Options {
+    options: Vec<EngineOption>,
+}
+
+impl Options {
+    /// Creates the registry without any options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the option with its default value, replacing any option with the same name.
+    ///
+    /// The default of the spin option must be within its limits.
+    pub fn register(&mut self, name: &str, kind: OptionKind) {
+        if let OptionKind::Spin { default, min, max } = kind {
+            debug_assert!(
+                (min..=max).contains(&default),
+                "default {default} of option '{name}' outside {min}..={max}"
+            );
+        }
+        let option = EngineOption {
+            name: name.to_string(),
+            value: kind.default_value(),
+            kind,
+            changed: false,
+        };
+        match self.find_mut(name) {
+            Some(old) => *old = option,
+            None => self.options.push(option),
+        }
+    }
+
+    /// Returns all the options, in the order of registration.
+    pub fn iter(&self) -> impl Iterator<Item = &EngineOption> {
+        self.options.iter()
+    }
+
+    /// Returns the option with the `name`.
+    pub fn get(&self, name: &str) -> Option<&EngineOption> {
+        self.options
+            .iter()
+            .find(|option| option.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the value of the check option with the `name`.
+    pub fn check(&self, name: &str) -> Option<bool> {
+        match self.get(name)?.value {
+            OptionValue::Check(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the spin option with the `name`.
+    pub fn spin(&self, name: &str) -> Option<i64> {
+        match self.get(name)?.value {
+            OptionValue::Spin(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the combo or string option with the `name`.
+    pub fn string(&self, name: &str) -> Option<&str> {
+        match &self.get(name)?.value {
+            OptionValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Sets the option with the `name` from the text `value`, checking it against the option type.
+    ///
+    /// The value of the buttons is ignored, setting them just marks them as pressed.
+    /// Combo values are matched case-insensitively, and `<empty>` sets an empty string.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), OptionError> {
+        let option = self
+            .find_mut(name)
+            .ok_or_else(|| OptionError::UnknownOption(name.to_string()))?;
+        let invalid = || OptionError::InvalidValue {
+            name: option.name.clone(),
+            value: value.to_string(),
+        };
+        let new = match &option.kind {
+            OptionKind::Check { .. } => match value.to_ascii_lowercase().as_str() {
+                "true" => OptionValue::Check(true),
+                "false" => OptionValue::Check(false),
+                _ => return Err(invalid()),
+            },
+            OptionKind::Spin { min, max, .. } => match value.parse() {
+                Ok(number) if (*min..=*max).contains(&number) => OptionValue::Spin(number),
+                _ => return Err(invalid()),
+            },
+            OptionKind::Combo { choices, .. } => {
+                let choice = choices
+                    .iter()
+                    .find(|choice| choice.eq_ignore_ascii_case(value))
+                    .ok_or_else(invalid)?;
+                OptionValue::String(choice.clone())
+            }
+            OptionKind::Button => OptionValue::Button,
+            OptionKind::String { .. } if value == "<empty>" => OptionValue::String(String::new()),
+            OptionKind::String { .. } => OptionValue::String(value.to_string()),
+        };
+        option.value = new;
+        option.changed = true;
+        Ok(())
+    }
+
+    /// Handles the `setoption name <name> [value <value>]` command, returning the name of the changed option.
+    pub fn set_from_command(&mut self, command: &str) -> Result<String, OptionError> {
+        let malformed = || OptionError::Malformed(command.to_string());
+        let rest = command
+            .trim()
+            .strip_prefix("setoption")
+            .filter(|rest| rest.starts_with(char::is_whitespace))
+            .and_then(|rest| rest.trim_start().strip_prefix("name "))
+            .ok_or_else(malformed)?;
+        let rest = rest.trim_end();
+        // The value may be empty, leaving just the keyword at the end.
+        let (name, value) = match rest.split_once(" value ") {
+            Some((name, value)) => (name.trim(), value.trim()),
+            None => (rest.strip_suffix(" value").unwrap_or(rest).trim(), ""),
+        };
+        if name.is_empty() {
+            return Err(malformed());
+        }
+        self.set(name, value)?;
+        Ok(self.get(name).unwrap().name.clone())
+    }
+
+    /// Tells if the option with the `name` was set since the last call, e.g. a button was pressed.
+    pub fn take_changed(&mut self, name: &str) -> bool {
+        match self.find_mut(name) {
+            Some(option) => core::mem::take(&mut option.changed),
+            None => false,
+        }
+    }
+
+    /// Returns the option with the `name`, for changing it.
+    fn find_mut(&mut self, name: &str) -> Option<&mut EngineOption> {
+        self.options
+            .iter_mut()
+            .find(|option| option.name.eq_ignore_ascii_case(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> Options {
+        let mut options = Options::new();
+        options.register(
+            "Hash",
+            OptionKind::Spin {
+                default: 16,
+                min: 1,
+                max: 1024,
+            },
+        );
+        options.register(
+            "EvalFile",
+            OptionKind::String {
+                default: "nn.bin".to_string(),
+            },
+        );
+        options.register("Clear Hash", OptionKind::Button);
+        options
+    }
+
+    #[test]
+    fn set_from_command() {
+        let mut options = options();
+        assert_eq!(
+            options.set_from_command("setoption name hash value 64"),
+            Ok("Hash".to_string())
+        );
+        assert_eq!(options.spin("Hash"), Some(64));
+        assert!(options.take_changed("Hash"));
+        assert!(!options.take_changed("Hash"));
+        assert_eq!(
+            options.set_from_command("setoption name Hash value 0"),
+            Err(OptionError::InvalidValue {
+                name: "Hash".to_string(),
+                value: "0".to_string()
+            })
+        );
+        assert_eq!(
+            options.set_from_command("setoption  name  Clear Hash "),
+            Ok("Clear Hash".to_string())
+        );
+        assert!(options.take_changed("Clear Hash"));
+        assert_eq!(
+            options.set_from_command("setoption name Threads value 2"),
+            Err(OptionError::UnknownOption("Threads".to_string()))
+        );
+        for command in [
+            "setoption value 2",
+            "setoptionname Hash value 32",
+            "setoptions name Hash value 32",
+            "setoption nameHash value 32",
+            "setoption name",
+        ] {
+            assert!(
+                matches!(
+                    options.set_from_command(command),
+                    Err(OptionError::Malformed(_))
+                ),
+                "{command}"
+            );
+        }
+        assert_eq!(options.spin("Hash"), Some(64));
+        assert_eq!(
+            options.set_from_command("setoption\tname Hash value 32"),
+            Ok("Hash".to_string())
+        );
+    }
+
+    #[test]
+    fn set_empty_value() {
+        for command in [
+            "setoption name EvalFile value",
+            "setoption name EvalFile value ",
+            "setoption name EvalFile",
+            "setoption name EvalFile value <empty>",
+        ] {
+            let mut options = options();
+            assert_eq!(
+                options.set_from_command(command),
+                Ok("EvalFile".to_string()),
+                "{command}"
+            );
+            assert_eq!(options.string("EvalFile"), Some(""), "{command}");
+        }
+        let mut options = options();
+        options
+            .set_from_command("setoption name EvalFile value my net.bin")
+            .unwrap();
+        assert_eq!(options.string("EvalFile"), Some("my net.bin"));
+    }
+}
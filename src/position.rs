@@ -0,0 +1,451 @@
+//! Placement of the pieces on the board.
+//!
+
+use crate::eval::{self, PhaseScore};
+use crate::{zobrist, Bitboard, Color, Field, File, Piece, Rank};
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt::Display;
+use core::str::FromStr;
+use strum::IntoEnumIterator;
+
+/// Reasons why a FEN string can't be parsed.
+///
+/// The offsets are in bytes from the start of the string.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FenError {
+    /// The string doesn't have the piece placement, the side to move, the castling rights and the en passant field.
+    MissingField,
+    /// Unknown piece letter in the piece placement.
+    InvalidPiece { letter: char, offset: usize },
+    /// The piece placement doesn't have eight ranks of eight fields each, and goes wrong at the offset.
+    InvalidBoard { offset: usize },
+    /// The side to move at the offset is neither `w` nor `b`.
+    InvalidSideToMove { offset: usize },
+    /// The halfmove clock at the offset isn't a number up to [MAX_HALFMOVE_CLOCK].
+    InvalidHalfmoveClock { offset: usize },
+}
+
+/// Largest halfmove clock accepted in a FEN, far more than any game can reach.
+pub const MAX_HALFMOVE_CLOCK: u32 = 10_000;
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FenError::MissingField => write!(f, "missing FEN field"),
+            FenError::InvalidPiece { letter, offset } => {
+                write!(f, "invalid piece letter '{letter}' at offset {offset}")
+            }
+            FenError::InvalidBoard { offset } => write!(
+                f,
+                "piece placement must have eight ranks of eight fields, wrong at offset {offset}"
+            ),
+            FenError::InvalidSideToMove { offset } => {
+                write!(f, "side to move must be 'w' or 'b', at offset {offset}")
+            }
+            FenError::InvalidHalfmoveClock { offset } => {
+                write!(f, "halfmove clock must be a number up to {MAX_HALFMOVE_CLOCK}, at offset {offset}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for FenError {}
+
+/// Chess position.
+///
+/// Keeps one bitboard per each piece of each color, the side to move,
+/// and the number of half-moves since the last capture or pawn move.
+///
+/// The material and piece-square evaluation, and the Zobrist keys, are updated each time
+/// a piece is put or removed, so they don't need to go through the whole board.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct Position {
+    pieces: [[Bitboard; 6]; 2],
+    side_to_move: Color,
+    halfmove_clock: u32,
+    score: PhaseScore,
+    key: u64,
+    pawn_key: u64,
+}
+
+impl Position {
+    /// Creates an empty board with white to move.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Puts the `piece` of the `color` on the `field`, replacing anything standing there.
+    ///
+    /// Nothing is put on the invalid field.
+    pub fn put(&mut self, color: Color, piece: Piece, field: Field) {
+        if field == Field::INVALID {
+            return;
+        }
+        self.remove(field);
+        self.pieces[color as usize][piece as usize].set(field);
+        self.score += eval::piece_score(color, piece, field);
+        self.key ^= zobrist::piece_key(color, piece, field);
+        if piece == Piece::Pawn {
+            self.pawn_key ^= zobrist::piece_key(color, piece, field);
+        }
+    }
+
+    /// Removes the piece standing on the `field`, if any.
+    pub fn remove(&mut self, field: Field) {
+        if let Some((color, piece)) = self.piece_at(field) {
+            self.pieces[color as usize][piece as usize].unset(field);
+            self.score -= eval::piece_score(color, piece, field);
+            self.key ^= zobrist::piece_key(color, piece, field);
+            if piece == Piece::Pawn {
+                self.pawn_key ^= zobrist::piece_key(color, piece, field);
+            }
+        }
+    }
+
+    /// Returns the piece standing on the `field`.
+    pub fn piece_at(&self, field: Field) -> Option<(Color, Piece)> {
+        Color::iter()
+            .flat_map(|color| Piece::iter().map(move |piece| (color, piece)))
+            .find(|&(color, piece)| self.pieces(color, piece).is_set(field))
+    }
+
+    /// Returns the fields occupied by the `piece` of the `color`.
+    pub fn pieces(&self, color: Color, piece: Piece) -> Bitboard {
+        self.pieces[color as usize][piece as usize]
+    }
+
+    /// Returns the fields occupied by any piece of the `color`.
+    pub fn occupied_by(&self, color: Color) -> Bitboard {
+        self.pieces[color as usize]
+            .iter()
+            .fold(Bitboard::default(), |acc, &board| acc | board)
+    }
+
+    /// Returns the fields occupied by any piece.
+    pub fn occupied(&self) -> Bitboard {
+        self.occupied_by(Color::White) | self.occupied_by(Color::Black)
+    }
+
+    /// Returns the color which moves next.
+    pub fn side_to_move(&self) -> Color {
+        self.side_to_move
+    }
+
+    /// Returns the number of half-moves since the last capture or pawn move, for the fifty-move rule.
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    /// Sets the number of half-moves since the last capture or pawn move.
+    pub fn set_halfmove_clock(&mut self, clock: u32) {
+        self.halfmove_clock = clock;
+    }
+
+    /// Returns the Zobrist key of the pieces and the side to move.
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
+    /// Returns the Zobrist key of the pawns only, used for caching the pawn structure evaluation.
+    pub fn pawn_key(&self) -> u64 {
+        self.pawn_key
+    }
+
+    /// Returns the white point of view material and piece-square score, for the default parameters.
+    pub(crate) fn incremental_score(&self) -> PhaseScore {
+        self.score
+    }
+
+    /// Sets the color which moves next.
+    pub fn set_side_to_move(&mut self, color: Color) {
+        if self.side_to_move != color {
+            self.key ^= zobrist::side_key();
+        }
+        self.side_to_move = color;
+    }
+
+    /// Returns the position with the colors swapped and the board flipped vertically.
+    ///
+    /// The mirrored position is the same for the other side, so it must get the same evaluation.
+    pub fn mirrored(&self) -> Self {
+        let mut mirrored = Position::new();
+        for color in Color::iter() {
+            for piece in Piece::iter() {
+                for field in self.pieces(color, piece).set_fields_iter() {
+                    mirrored.put(!color, piece, field.relative(Color::Black));
+                }
+            }
+        }
+        mirrored.set_side_to_move(!self.side_to_move);
+        mirrored.set_halfmove_clock(self.halfmove_clock);
+        mirrored
+    }
+
+    /// Parses the position from the Forsyth-Edwards Notation.
+    ///
+    /// The castling rights and the en passant field aren't kept, so they are only checked to be present.
+    /// The halfmove clock and the fullmove number may be left out.
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let mut fields = fen.split_whitespace();
+        let mut next = || fields.next().ok_or(FenError::MissingField);
+        let (placement, side, _castling, _en_passant) = (next()?, next()?, next()?, next()?);
+
+        // The fields are slices of the string, so their offsets are the distances between the pointers.
+        let offset_of = |field: &str| field.as_ptr() as usize - fen.as_ptr() as usize;
+
+        let mut position = Position::new();
+        let mut offset = offset_of(placement);
+        let mut rows: u8 = 0;
+        for pieces in placement.split('/') {
+            if rows == 8 {
+                return Err(FenError::InvalidBoard { offset });
+            }
+            let rank = Rank::from(7 - rows);
+            let mut file: u32 = 0;
+            for letter in pieces.chars() {
+                match letter.to_digit(10).filter(|&empty| empty > 0) {
+                    Some(empty) => file += empty,
+                    None => {
+                        let (color, piece) = piece_from_letter(letter)
+                            .ok_or(FenError::InvalidPiece { letter, offset })?;
+                        if file < 8 {
+                            position.put(color, piece, Field::new(File::from(file as u8), rank));
+                        }
+                        file += 1;
+                    }
+                }
+                if file > 8 {
+                    return Err(FenError::InvalidBoard { offset });
+                }
+                offset += letter.len_utf8();
+            }
+            if file != 8 {
+                return Err(FenError::InvalidBoard { offset });
+            }
+            rows += 1;
+            // The slash.
+            offset += 1;
+        }
+        if rows != 8 {
+            return Err(FenError::InvalidBoard { offset: offset - 1 });
+        }
+
+        position.set_side_to_move(match side {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => {
+                return Err(FenError::InvalidSideToMove {
+                    offset: offset_of(side),
+                })
+            }
+        });
+        if let Ok(clock) = next() {
+            let clock = clock
+                .parse()
+                .ok()
+                .filter(|&clock| clock <= MAX_HALFMOVE_CLOCK)
+                .ok_or(FenError::InvalidHalfmoveClock {
+                    offset: offset_of(clock),
+                })?;
+            position.set_halfmove_clock(clock);
+        }
+        Ok(position)
+    }
+
+    /// Writes the position in the Forsyth-Edwards Notation.
+    ///
+    /// The castling rights, the en passant field and the fullmove number aren't kept,
+    /// so they are written as `- - 1`.
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+        for rank in (0..8u8).rev() {
+            let mut empty = 0;
+            for file in 0..8u8 {
+                match self.piece_at(Field::new(File::from(file), Rank::from(rank))) {
+                    Some((color, piece)) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push(piece_letter(color, piece));
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+        let side = match self.side_to_move() {
+            Color::White => 'w',
+            Color::Black => 'b',
+        };
+        fen + &format!(" {side} - - {} 1", self.halfmove_clock())
+    }
+}
+
+/// Draws the board with the rank 8 at the top, followed by the FEN and the key.
+impl Display for Position {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for rank in (0..8u8).rev() {
+            write!(f, "{} ", rank + 1)?;
+            for file in 0..8u8 {
+                let letter = match self.piece_at(Field::new(File::from(file), Rank::from(rank))) {
+                    Some((color, piece)) => piece_letter(color, piece),
+                    None => '.',
+                };
+                write!(f, "{letter}")?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "  ABCDEFGH")?;
+        writeln!(f, "Fen: {}", self.to_fen())?;
+        writeln!(f, "Key: {:016x}", self.key())
+    }
+}
+
+impl FromStr for Position {
+    type Err = FenError;
+
+    fn from_str(fen: &str) -> Result<Self, Self::Err> {
+        Position::from_fen(fen)
+    }
+}
+
+/// Returns the piece written as the `letter` in the FEN, uppercase for white.
+fn piece_from_letter(letter: char) -> Option<(Color, Piece)> {
+    let color = match letter.is_ascii_uppercase() {
+        true => Color::White,
+        false => Color::Black,
+    };
+    let piece = match letter.to_ascii_lowercase() {
+        'p' => Piece::Pawn,
+        'n' => Piece::Knight,
+        'b' => Piece::Bishop,
+        'r' => Piece::Rook,
+        'q' => Piece::Queen,
+        'k' => Piece::King,
+        _ => return None,
+    };
+    Some((color, piece))
+}
+
+/// Returns the letter of the piece in the FEN, uppercase for white.
+fn piece_letter(color: Color, piece: Piece) -> char {
+    let letter = match piece {
+        Piece::Pawn => 'p',
+        Piece::Knight => 'n',
+        Piece::Bishop => 'b',
+        Piece::Rook => 'r',
+        Piece::Queen => 'q',
+        Piece::King => 'k',
+    };
+    match color {
+        Color::White => letter.to_ascii_uppercase(),
+        Color::Black => letter,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_field_is_ignored() {
+        let mut position = Position::new();
+        position.put(Color::White, Piece::Queen, Field::INVALID);
+        position.remove(Field::INVALID);
+        assert_eq!(position, Position::new());
+        assert_eq!(position.piece_at(Field::INVALID), None);
+        assert!(position.piece_at(Field::H8).is_none());
+    }
+
+    #[test]
+    fn fen_error_offsets() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR";
+        for (fen, error) in [
+            ("", FenError::MissingField),
+            (start, FenError::MissingField),
+            (
+                "rnbqkbnr/pppxpppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - -",
+                FenError::InvalidPiece {
+                    letter: 'x',
+                    offset: 12,
+                },
+            ),
+            (
+                "  rnbqkbnr/pppxpppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - -",
+                FenError::InvalidPiece {
+                    letter: 'x',
+                    offset: 14,
+                },
+            ),
+            (
+                "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w - -",
+                FenError::InvalidBoard { offset: 18 },
+            ),
+            (
+                "rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - -",
+                FenError::InvalidBoard { offset: 16 },
+            ),
+            ("8/8/8/8/8/8/8 w - -", FenError::InvalidBoard { offset: 13 }),
+            (
+                "8/8/8/8/8/8/8/8/8 w - -",
+                FenError::InvalidBoard { offset: 16 },
+            ),
+            (
+                "8/8/8/8/8/8/8/0 w - -",
+                FenError::InvalidPiece {
+                    letter: '0',
+                    offset: 14,
+                },
+            ),
+            (
+                &format!("{start} x - -"),
+                FenError::InvalidSideToMove { offset: 44 },
+            ),
+            (
+                "8/8/8/8/8/8/8/8 w - - x 1",
+                FenError::InvalidHalfmoveClock { offset: 22 },
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4KQ2 w - - 3000000000 1",
+                FenError::InvalidHalfmoveClock { offset: 27 },
+            ),
+            (
+                "8/8/8/8/8/8/8/8 w - - 10001 1",
+                FenError::InvalidHalfmoveClock { offset: 22 },
+            ),
+        ] {
+            assert_eq!(Position::from_fen(fen), Err(error), "{fen}");
+        }
+    }
+
+    #[test]
+    fn fen_round_trip() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+            "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR b - - 4 1",
+            "8/8/4k3/8/2P5/8/5K2/8 w - - 99 1",
+            "8/8/8/8/8/8/8/8 b - - 0 1",
+        ] {
+            let position = Position::from_fen(fen).unwrap();
+            assert_eq!(position.to_fen(), fen);
+            assert_eq!(Position::from_fen(&position.to_fen()), Ok(position));
+        }
+        let position = Position::from_fen("4k3/8/8/8/8/8/8/4K2R b K - 7 40").unwrap();
+        assert_eq!(position.side_to_move(), Color::Black);
+        assert_eq!(position.halfmove_clock(), 7);
+        assert_eq!(
+            position.piece_at(Field::H1),
+            Some((Color::White, Piece::Rook))
+        );
+        assert_eq!(position.to_fen(), "4k3/8/8/8/8/8/8/4K2R b - - 7 1");
+        let position = Position::from_fen("4k3/8/8/8/8/8/8/4K3 w KQkq e3").unwrap();
+        assert_eq!(position.halfmove_clock(), 0);
+    }
+}
@@ -0,0 +1,256 @@
+//! Drawing positions as SVG diagrams.
+//!
+//! The diagram has the board with the pieces, optionally the coordinates around it,
+//! the fields of the last move highlighted, and arrows between the fields.
+//! The pieces are drawn with text, so they look like the font of the viewer.
+//!
+
+use crate::{Color, Field, Piece, Position};
+use alloc::string::String;
+use core::fmt::Write;
+
+/// How the pieces are drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PieceSet {
+    /// The chess symbols, like `♔` and `♚`.
+    Unicode,
+    /// The FEN letters, white ones filled with the light color and outlined.
+    Letters,
+}
+
+/// Look of the diagram, the sizes in pixels and the colors in the SVG format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SvgStyle {
+    /// Size of a single field.
+    pub field_size: u32,
+    pub light: &'static str,
+    pub dark: &'static str,
+    /// Color laid over the fields of the last move.
+    pub highlight: &'static str,
+    pub arrow: &'static str,
+    pub pieces: PieceSet,
+    /// Draw the file letters and the rank numbers around the board.
+    pub coordinates: bool,
+    /// Draw the board from the black side.
+    pub flipped: bool,
+}
+
+impl SvgStyle {
+    /// The default look.
+    pub const DEFAULT: Self = Self {
+        field_size: 45,
+        light: "#f0d9b5",
+        dark: "#b58863",
+        highlight: "rgba(155, 199, 0, 0.41)",
+        arrow: "rgba(21, 120, 27, 0.8)",
+        pieces: PieceSet::Unicode,
+        coordinates: true,
+        flipped: false,
+    };
+}
+
+impl Default for SvgStyle {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Draws the `position`, with the `last_move` fields highlighted and the `arrows` from field to field.
+///
+/// Arrows from or to the invalid field are left out.
+pub fn svg(
+    position: &Position,
+    last_move: Option<(Field, Field)>,
+    arrows: &[(Field, Field)],
+    style: &SvgStyle,
+) -> String {
+    let mut out = String::new();
+    // Writing to a string never fails.
+    let _ = write_svg(&mut out, position, last_move, arrows, style);
+    out
+}
+
+fn write_svg(
+    out: &mut String,
+    position: &Position,
+    last_move: Option<(Field, Field)>,
+    arrows: &[(Field, Field)],
+    style: &SvgStyle,
+) -> core::fmt::Result {
+    let size = style.field_size;
+    let margin = match style.coordinates {
+        true => size / 2,
+        false => 0,
+    };
+    let total = 8 * size + 2 * margin;
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{total}" height="{total}" viewBox="0 0 {total} {total}">"#
+    )?;
+    let arrows = arrows
+        .iter()
+        .filter(|(from, to)| *from != Field::INVALID && *to != Field::INVALID);
+    if arrows.clone().next().is_some() {
+        writeln!(
+            out,
+            r#"<defs><marker id="arrowhead" markerWidth="4" markerHeight="4" refX="2" refY="2" orient="auto"><path d="M0,0 L4,2 L0,4 Z" fill="{}"/></marker></defs>"#,
+            style.arrow
+        )?;
+    }
+
+    for index in 0..64u8 {
+        let field = Field::from(index);
+        let (x, y) = corner(field, style, margin);
+        let light = (field.file() as u8 + field.rank() as u8) % 2 == 1;
+        let color = match light {
+            true => style.light,
+            false => style.dark,
+        };
+        writeln!(
+            out,
+            r#"<rect x="{x}" y="{y}" width="{size}" height="{size}" fill="{color}"/>"#
+        )?;
+        if let Some((from, to)) = last_move {
+            if field == from || field == to {
+                writeln!(
+                    out,
+                    r#"<rect x="{x}" y="{y}" width="{size}" height="{size}" fill="{}"/>"#,
+                    style.highlight
+                )?;
+            }
+        }
+    }
+
+    if style.coordinates {
+        let font = size / 3;
+        for line in 0..8u32 {
+            let (file, rank) = match style.flipped {
+                true => (b'h' - line as u8, b'1' + line as u8),
+                false => (b'a' + line as u8, b'8' - line as u8),
+            };
+            let center = margin + line * size + size / 2;
+            let bottom = total - margin / 2;
+            writeln!(
+                out,
+                r#"<text x="{center}" y="{bottom}" font-size="{font}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                file as char
+            )?;
+            writeln!(
+                out,
+                r#"<text x="{}" y="{center}" font-size="{font}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                margin / 2,
+                rank as char
+            )?;
+        }
+    }
+
+    for index in 0..64u8 {
+        let field = Field::from(index);
+        let Some((color, piece)) = position.piece_at(field) else {
+            continue;
+        };
+        let (x, y) = center(field, style, margin);
+        let font = size * 4 / 5;
+        match style.pieces {
+            PieceSet::Unicode => writeln!(
+                out,
+                r#"<text x="{x}" y="{y}" font-size="{font}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                symbol(color, piece)
+            )?,
+            PieceSet::Letters => {
+                let fill = match color {
+                    Color::White => style.light,
+                    Color::Black => "black",
+                };
+                writeln!(
+                    out,
+                    r#"<text x="{x}" y="{y}" font-size="{font}" font-weight="bold" text-anchor="middle" dominant-baseline="central" fill="{fill}" stroke="black">{}</text>"#,
+                    letter(piece)
+                )?
+            }
+        }
+    }
+
+    for &(from, to) in arrows {
+        let (x1, y1) = center(from, style, margin);
+        let (x2, y2) = center(to, style, margin);
+        writeln!(
+            out,
+            r#"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" stroke="{}" stroke-width="{}" stroke-linecap="round" marker-end="url(#arrowhead)"/>"#,
+            style.arrow,
+            size / 6
+        )?;
+    }
+
+    writeln!(out, "</svg>")
+}
+
+/// Returns the top left corner of the `field`.
+fn corner(field: Field, style: &SvgStyle, margin: u32) -> (u32, u32) {
+    let (file, rank) = (field.file() as u32, field.rank() as u32);
+    let (column, row) = match style.flipped {
+        true => (7 - file, rank),
+        false => (file, 7 - rank),
+    };
+    (
+        margin + column * style.field_size,
+        margin + row * style.field_size,
+    )
+}
+
+/// Returns the center of the `field`.
+fn center(field: Field, style: &SvgStyle, margin: u32) -> (u32, u32) {
+    let (x, y) = corner(field, style, margin);
+    (x + style.field_size / 2, y + style.field_size / 2)
+}
+
+/// Returns the chess symbol of the piece.
+fn symbol(color: Color, piece: Piece) -> char {
+    match (color, piece) {
+        (Color::White, Piece::King) => '♔',
+        (Color::White, Piece::Queen) => '♕',
+        (Color::White, Piece::Rook) => '♖',
+        (Color::White, Piece::Bishop) => '♗',
+        (Color::White, Piece::Knight) => '♘',
+        (Color::White, Piece::Pawn) => '♙',
+        (Color::Black, Piece::King) => '♚',
+        (Color::Black, Piece::Queen) => '♛',
+        (Color::Black, Piece::Rook) => '♜',
+        (Color::Black, Piece::Bishop) => '♝',
+        (Color::Black, Piece::Knight) => '♞',
+        (Color::Black, Piece::Pawn) => '♟',
+    }
+}
+
+/// Returns the uppercase FEN letter of the piece.
+fn letter(piece: Piece) -> char {
+    match piece {
+        Piece::Pawn => 'P',
+        Piece::Knight => 'N',
+        Piece::Bishop => 'B',
+        Piece::Rook => 'R',
+        Piece::Queen => 'Q',
+        Piece::King => 'K',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_fields_are_skipped() {
+        let position = Position::new();
+        let style = SvgStyle::DEFAULT;
+        let plain = svg(&position, None, &[], &style);
+        let drawn = svg(
+            &position,
+            Some((Field::INVALID, Field::INVALID)),
+            &[(Field::INVALID, Field::E4), (Field::E2, Field::INVALID)],
+            &style,
+        );
+        assert_eq!(drawn, plain);
+        let arrow = svg(&position, None, &[(Field::E2, Field::E4)], &style);
+        assert_eq!(arrow.matches("<line").count(), 1);
+    }
+}
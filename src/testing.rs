@@ -0,0 +1,283 @@
+//! Slow and simple reference implementations, for testing the fast ones against.
+//!
+//! The moves are generated on a 10x12 mailbox: the board surrounded by the guard fields,
+//! two rows above and below and one column on each side, so a step leaving the board
+//! always lands on a guard. The mailbox doesn't share any code with the bitboard [attacks](crate::attacks),
+//! so [check_attacks] compares the two, and [perft] counts the moves for comparing with the known numbers.
+//!
+//! Castling and en passant aren't generated, as [Position] doesn't keep the castling rights
+//! nor the en passant field.
+//!
+
+use crate::attacks::piece_attacks;
+use crate::{Bitboard, Color, Field, Piece, Position};
+use alloc::vec::Vec;
+use core::fmt::Display;
+use strum::IntoEnumIterator;
+
+/// Steps of the knight on the mailbox.
+const KNIGHT_STEPS: [isize; 8] = [-21, -19, -12, -8, 8, 12, 19, 21];
+/// Steps of the bishop on the mailbox, repeated until blocked.
+const BISHOP_STEPS: [isize; 4] = [-11, -9, 9, 11];
+/// Steps of the rook on the mailbox, repeated until blocked.
+const ROOK_STEPS: [isize; 4] = [-10, -1, 1, 10];
+/// Steps of the king and the queen on the mailbox.
+const ROYAL_STEPS: [isize; 8] = [-11, -10, -9, -1, 1, 9, 10, 11];
+
+/// Pieces a pawn can be promoted to.
+const PROMOTIONS: [Piece; 4] = [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight];
+
+/// Move from field to field, with the piece a pawn is promoted to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Move {
+    pub from: Field,
+    pub to: Field,
+    pub promotion: Option<Piece>,
+}
+
+/// Formats the move in the UCI notation, like `e2e4` or `e7e8q`.
+impl Display for Move {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for field in [self.from, self.to] {
+            let file = (b'a' + field.file() as u8) as char;
+            let rank = (b'1' + field.rank() as u8) as char;
+            write!(f, "{file}{rank}")?;
+        }
+        match self.promotion {
+            Some(Piece::Knight) => write!(f, "n"),
+            Some(Piece::Bishop) => write!(f, "b"),
+            Some(Piece::Rook) => write!(f, "r"),
+            Some(_) => write!(f, "q"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Attacks of a piece which differ between the bitboards and the mailbox.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AttackMismatch {
+    pub field: Field,
+    pub bitboard: Bitboard,
+    pub mailbox: Bitboard,
+}
+
+/// Returns the index of the `field` on the mailbox.
+fn to_mailbox(field: Field) -> isize {
+    let index = field as isize;
+    (index / 8 + 2) * 10 + index % 8 + 1
+}
+
+/// Returns the field with the mailbox `index`, or nothing for the guard fields.
+fn from_mailbox(index: isize) -> Option<Field> {
+    let (row, column) = (index / 10 - 2, index % 10 - 1);
+    match (0..8).contains(&row) && (0..8).contains(&column) && index >= 0 {
+        true => Some(Field::from((row * 8 + column) as u8)),
+        false => None,
+    }
+}
+
+/// Returns the fields reached from the `field` with the `steps`, repeated until blocked when `slide` is set.
+fn reached(position: &Position, field: Field, steps: &[isize], slide: bool) -> Vec<Field> {
+    let mut fields = Vec::new();
+    for step in steps {
+        let mut index = to_mailbox(field);
+        loop {
+            index += step;
+            let Some(target) = from_mailbox(index) else {
+                break;
+            };
+            fields.push(target);
+            if !slide || position.piece_at(target).is_some() {
+                break;
+            }
+        }
+    }
+    fields
+}
+
+/// Returns the fields attacked by the piece standing on the `field`, none for an empty one.
+pub fn mailbox_attacks(position: &Position, field: Field) -> Vec<Field> {
+    let Some((color, piece)) = position.piece_at(field) else {
+        return Vec::new();
+    };
+    match piece {
+        Piece::Pawn => match color {
+            Color::White => reached(position, field, &[9, 11], false),
+            Color::Black => reached(position, field, &[-11, -9], false),
+        },
+        Piece::Knight => reached(position, field, &KNIGHT_STEPS, false),
+        Piece::Bishop => reached(position, field, &BISHOP_STEPS, true),
+        Piece::Rook => reached(position, field, &ROOK_STEPS, true),
+        Piece::Queen => reached(position, field, &ROYAL_STEPS, true),
+        Piece::King => reached(position, field, &ROYAL_STEPS, false),
+    }
+}
+
+/// Tells if any piece of the `color` attacks the `field`.
+pub fn is_attacked(position: &Position, field: Field, color: Color) -> bool {
+    (0..64u8).map(Field::from).any(|from| {
+        matches!(position.piece_at(from), Some((found, _)) if found == color)
+            && mailbox_attacks(position, from).contains(&field)
+    })
+}
+
+/// Returns the moves which don't leave the king of the side to move attacked.
+pub fn legal_moves(position: &Position) -> Vec<Move> {
+    let side = position.side_to_move();
+    let mut moves = Vec::new();
+    for from in (0..64u8).map(Field::from) {
+        match position.piece_at(from) {
+            Some((color, Piece::Pawn)) if color == side => pawn_moves(position, from, &mut moves),
+            Some((color, _)) if color == side => {
+                for to in mailbox_attacks(position, from) {
+                    if !matches!(position.piece_at(to), Some((color, _)) if color == side) {
+                        moves.push(Move {
+                            from,
+                            to,
+                            promotion: None,
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    moves.retain(|&mv| {
+        let after = make_move(position, mv);
+        let king = (0..64u8)
+            .map(Field::from)
+            .find(|&field| after.piece_at(field) == Some((side, Piece::King)));
+        king.is_none_or(|king| !is_attacked(&after, king, !side))
+    });
+    moves
+}
+
+/// Adds the pushes and the captures of the pawn on the `from` field, with all the promotions on the last rank.
+fn pawn_moves(position: &Position, from: Field, moves: &mut Vec<Move>) {
+    let side = position.side_to_move();
+    let (forward, start, last) = match side {
+        Color::White => (10, 1, 7),
+        Color::Black => (-10, 6, 0),
+    };
+    let mut targets = Vec::new();
+    let one = from_mailbox(to_mailbox(from) + forward);
+    if let Some(one) = one.filter(|&one| position.piece_at(one).is_none()) {
+        targets.push(one);
+        let two = from_mailbox(to_mailbox(one) + forward);
+        if let Some(two) = two.filter(|&two| position.piece_at(two).is_none()) {
+            if from.rank() as u8 == start {
+                targets.push(two);
+            }
+        }
+    }
+    for to in mailbox_attacks(position, from) {
+        if matches!(position.piece_at(to), Some((color, _)) if color != side) {
+            targets.push(to);
+        }
+    }
+    for to in targets {
+        match to.rank() as u8 == last {
+            true => moves.extend(PROMOTIONS.map(|piece| Move {
+                from,
+                to,
+                promotion: Some(piece),
+            })),
+            false => moves.push(Move {
+                from,
+                to,
+                promotion: None,
+            }),
+        }
+    }
+}
+
+/// Returns the position after the move, with the other side to move.
+pub fn make_move(position: &Position, mv: Move) -> Position {
+    let mut after = *position;
+    let Some((color, piece)) = position.piece_at(mv.from) else {
+        return after;
+    };
+    let capture = position.piece_at(mv.to).is_some();
+    after.remove(mv.from);
+    after.remove(mv.to);
+    after.put(color, mv.promotion.unwrap_or(piece), mv.to);
+    after.set_side_to_move(!color);
+    match capture || piece == Piece::Pawn {
+        true => after.set_halfmove_clock(0),
+        false => after.set_halfmove_clock(position.halfmove_clock() + 1),
+    }
+    after
+}
+
+/// Counts the move sequences of the `depth` from the position.
+pub fn perft(position: &Position, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    legal_moves(position)
+        .into_iter()
+        .map(|mv| perft(&make_move(position, mv), depth - 1))
+        .sum()
+}
+
+/// Checks that the bitboard attacks of each piece on the board match the mailbox ones.
+pub fn check_attacks(position: &Position) -> Result<(), AttackMismatch> {
+    let occupied = position.occupied();
+    for color in Color::iter() {
+        for piece in Piece::iter() {
+            for field in position.pieces(color, piece).set_fields_iter() {
+                let bitboard = piece_attacks(piece, color, field, occupied);
+                let mailbox = mailbox_attacks(position, field)
+                    .into_iter()
+                    .fold(Bitboard::default(), |acc, field| {
+                        acc | Bitboard::from(field)
+                    });
+                if bitboard != mailbox {
+                    return Err(AttackMismatch {
+                        field,
+                        bitboard,
+                        mailbox,
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::verify::random_positions;
+    use alloc::string::ToString;
+
+    #[test]
+    fn perft_from_start() {
+        let start =
+            Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1").unwrap();
+        assert_eq!([1, 2, 3].map(|depth| perft(&start, depth)), [20, 400, 8902]);
+    }
+
+    #[test]
+    fn special_moves() {
+        let promotion = Position::from_fen("8/P6k/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        let moves = legal_moves(&promotion);
+        assert_eq!(moves.len(), 7);
+        assert!(moves.iter().any(|mv| mv.to_string() == "a7a8n"));
+
+        let pinned = Position::from_fen("4k3/4r3/8/8/8/8/4B3/4K3 w - - 0 1").unwrap();
+        assert!(legal_moves(&pinned).iter().all(|mv| mv.from == Field::E1));
+        assert_eq!(legal_moves(&pinned).len(), 4);
+
+        let mate = Position::from_fen("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(legal_moves(&mate), []);
+        assert!(is_attacked(&mate, Field::H8, Color::White));
+    }
+
+    #[test]
+    fn bitboard_attacks_match_mailbox() {
+        for position in random_positions(5, 500) {
+            assert_eq!(check_attacks(&position), Ok(()), "{}", position.to_fen());
+        }
+    }
+}
@@ -0,0 +1,560 @@
+//! Time management, deciding how long to think about a move.
+//!
+//! The limits of the UCI `go` command are turned into two budgets. The search shouldn't start
+//! a new iteration after the soft one, and must stop at the hard one. The soft budget is scaled
+//! by how the search goes: it grows while the best move keeps changing or the score drops,
+//! and shrinks when the best move stays the same.
+//!
+//! While pondering there are no budgets. On `ponderhit` they are computed from the limits
+//! of the `go ponder` command, and the time already spent pondering counts against the soft one,
+//! while the hard one only starts from that moment, as the clock of the engine does.
+//! The stability of the pondering iterations is kept.
+//!
+//! The time lost on each move talking to the interface is set with the `Move Overhead` option.
+//! For the reproducible test games the `nodestime` option replaces the clock with the nodes
+//! searched, so each millisecond of the budgets stands for that many nodes.
+//!
+
+use crate::options::{OptionKind, Options};
+use crate::Color;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Limits given with the `go` command, all times in milliseconds.
+///
+/// Only the clock and the flags are used by the [TimeManager], the rest limits the search itself.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub wtime: Option<u64>,
+    pub btime: Option<u64>,
+    pub winc: Option<u64>,
+    pub binc: Option<u64>,
+    /// Moves until the next time control.
+    pub movestogo: Option<u32>,
+    /// Exact time of the search.
+    pub movetime: Option<u64>,
+    /// Largest depth to search, in plies.
+    pub depth: Option<u32>,
+    /// Largest number of nodes to search.
+    pub nodes: Option<u64>,
+    /// Search for a mate in the number of moves.
+    pub mate: Option<u32>,
+    /// Moves to search, in the UCI notation, all the moves when empty.
+    pub searchmoves: Vec<String>,
+    /// Search until stopped.
+    pub infinite: bool,
+    /// Search on the opponent's time, until `ponderhit` or `stop`.
+    pub ponder: bool,
+}
+
+/// Keywords of the `go` command, ending the list of the `searchmoves`.
+const KEYWORDS: [&str; 12] = [
+    "wtime",
+    "btime",
+    "winc",
+    "binc",
+    "movestogo",
+    "movetime",
+    "depth",
+    "nodes",
+    "mate",
+    "searchmoves",
+    "infinite",
+    "ponder",
+];
+
+impl Limits {
+    /// Parses the limits following `go`, skipping the unknown fields.
+    pub fn parse(fields: &str) -> Self {
+        let mut limits = Limits::default();
+        let mut tokens = fields.split_whitespace().peekable();
+        while let Some(token) = tokens.next() {
+            match token {
+                "wtime" => limits.wtime = next_number(&mut tokens),
+                "btime" => limits.btime = next_number(&mut tokens),
+                "winc" => limits.winc = next_number(&mut tokens),
+                "binc" => limits.binc = next_number(&mut tokens),
+                "movestogo" => limits.movestogo = next_number(&mut tokens),
+                "movetime" => limits.movetime = next_number(&mut tokens),
+                "depth" => limits.depth = next_number(&mut tokens),
+                "nodes" => limits.nodes = next_number(&mut tokens),
+                "mate" => limits.mate = next_number(&mut tokens),
+                "searchmoves" => {
+                    while let Some(mv) = tokens.next_if(|token| !KEYWORDS.contains(token)) {
+                        limits.searchmoves.push(mv.to_string());
+                    }
+                }
+                "infinite" => limits.infinite = true,
+                "ponder" => limits.ponder = true,
+                _ => {}
+            }
+        }
+        limits
+    }
+
+    /// Returns the remaining time and the increment of the `color`.
+    pub fn clock(&self, color: Color) -> (Option<u64>, u64) {
+        match color {
+            Color::White => (self.wtime, self.winc.unwrap_or(0)),
+            Color::Black => (self.btime, self.binc.unwrap_or(0)),
+        }
+    }
+}
+
+/// Parses the next token as a number.
+fn next_number<'a, T: FromStr>(tokens: &mut impl Iterator<Item = &'a str>) -> Option<T> {
+    tokens.next()?.parse().ok()
+}
+
+/// Formats the limits as the fields of the `go` command.
+impl Display for Limits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fields = [
+            ("wtime", self.wtime),
+            ("btime", self.btime),
+            ("winc", self.winc),
+            ("binc", self.binc),
+            ("movestogo", self.movestogo.map(u64::from)),
+            ("movetime", self.movetime),
+            ("depth", self.depth.map(u64::from)),
+            ("nodes", self.nodes),
+            ("mate", self.mate.map(u64::from)),
+        ];
+        let mut separator = "";
+        for (name, value) in fields {
+            if let Some(value) = value {
+                write!(f, "{separator}{name} {value}")?;
+                separator = " ";
+            }
+        }
+        for (name, set) in [("infinite", self.infinite), ("ponder", self.ponder)] {
+            if set {
+                write!(f, "{separator}{name}")?;
+                separator = " ";
+            }
+        }
+        if !self.searchmoves.is_empty() {
+            write!(f, "{separator}searchmoves {}", self.searchmoves.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Settings of the time manager, the times in milliseconds and the scales in percents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeParams {
+    /// Time lost on each move talking to the interface, never used for thinking.
+    pub overhead: u64,
+    /// Number of moves the remaining time is split into, when `movestogo` isn't given.
+    pub horizon: u32,
+    /// Part of the increment added to the soft budget.
+    pub increment: u64,
+    /// The hard budget relative to the soft one.
+    pub hard: u64,
+    /// Largest part of the remaining time used for one move.
+    pub max_usage: u64,
+    /// Scale of the soft budget when the best move has just changed.
+    pub unstable: u64,
+    /// Scale of the soft budget when the best move stayed the same for a few iterations.
+    pub stable: u64,
+    /// Number of iterations after which the best move counts as stable.
+    pub stable_iterations: u32,
+    /// Added to the scale of the soft budget when the score dropped.
+    pub fail_low: u64,
+    /// Nodes standing for a millisecond, measuring the time with the nodes searched when not zero.
+    pub nodestime: u64,
+}
+
+impl TimeParams {
+    /// The default settings.
+    pub const DEFAULT: Self = Self {
+        overhead: 30,
+        horizon: 30,
+        increment: 75,
+        hard: 400,
+        max_usage: 80,
+        unstable: 150,
+        stable: 70,
+        stable_iterations: 4,
+        fail_low: 50,
+        nodestime: 0,
+    };
+
+    /// Name of the option setting the [TimeParams::overhead].
+    pub const OVERHEAD_OPTION: &'static str = "Move Overhead";
+    /// Name of the option setting the [TimeParams::nodestime].
+    pub const NODESTIME_OPTION: &'static str = "nodestime";
+
+    /// Registers the time management options, see [TimeParams::configure].
+    pub fn register_options(options: &mut Options) {
+        options.register(
+            Self::OVERHEAD_OPTION,
+            OptionKind::Spin {
+                default: Self::DEFAULT.overhead as i64,
+                min: 0,
+                max: 5000,
+            },
+        );
+        options.register(
+            Self::NODESTIME_OPTION,
+            OptionKind::Spin {
+                default: Self::DEFAULT.nodestime as i64,
+                min: 0,
+                max: 10000,
+            },
+        );
+    }
+
+    /// Applies the time management options changed since the last call.
+    pub fn configure(&mut self, options: &mut Options) {
+        if options.take_changed(Self::OVERHEAD_OPTION) {
+            if let Some(overhead) = options.spin(Self::OVERHEAD_OPTION) {
+                self.overhead = overhead as u64;
+            }
+        }
+        if options.take_changed(Self::NODESTIME_OPTION) {
+            if let Some(nodestime) = options.spin(Self::NODESTIME_OPTION) {
+                self.nodestime = nodestime as u64;
+            }
+        }
+    }
+}
+
+impl Default for TimeParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Budgets of a single search, measured from its start.
+#[derive(Clone, Debug)]
+pub struct TimeManager {
+    params: TimeParams,
+    /// Kept for computing the budgets on `ponderhit`.
+    limits: Limits,
+    color: Color,
+    start: Instant,
+    /// Nodes searched so far, the clock when the [TimeParams::nodestime] is set.
+    nodes: u64,
+    /// No budgets mean searching until stopped.
+    soft: Option<Duration>,
+    hard: Option<Duration>,
+    stable_iterations: u32,
+    fail_low: bool,
+    pondering: bool,
+}
+
+impl TimeManager {
+    /// Starts timing the search of the side to move `color` with the `limits`.
+    pub fn new(limits: &Limits, color: Color, params: TimeParams) -> Self {
+        let mut manager = Self {
+            params,
+            limits: limits.clone(),
+            color,
+            start: Instant::now(),
+            nodes: 0,
+            soft: None,
+            hard: None,
+            stable_iterations: 0,
+            fail_low: false,
+            pondering: limits.ponder,
+        };
+        if !manager.pondering {
+            manager.set_budgets(Duration::ZERO);
+        }
+        manager
+    }
+
+    /// Computes the budgets from the limits, with the hard one starting after the `pondered` time.
+    fn set_budgets(&mut self, pondered: Duration) {
+        let budgets = budgets(&self.limits, self.color, &self.params);
+        self.soft = budgets.map(|(soft, _)| Duration::from_millis(soft));
+        self.hard = budgets.map(|(_, hard)| pondered.saturating_add(Duration::from_millis(hard)));
+    }
+
+    /// Returns the time since the search started, including the pondering.
+    ///
+    /// With the [TimeParams::nodestime] set, it's the time standing for the nodes searched.
+    pub fn elapsed(&self) -> Duration {
+        match self.params.nodestime {
+            0 => self.start.elapsed(),
+            nodestime => Duration::from_millis(self.nodes / nodestime),
+        }
+    }
+
+    /// Records the number of nodes searched since the search started.
+    pub fn set_nodes(&mut self, nodes: u64) {
+        self.nodes = nodes;
+    }
+
+    /// Tells if the search is still on the opponent's time.
+    pub fn pondering(&self) -> bool {
+        self.pondering
+    }
+
+    /// Switches from pondering to the normal search, after the opponent played the expected move.
+    ///
+    /// Returns the time spent pondering. The limits of the `go ponder` command are already
+    /// for the position after the expected move, so the budgets are computed from them now.
+    /// The pondered time is already used from the soft budget, but not from the hard one,
+    /// as the clock of the engine didn't run.
+    pub fn ponderhit(&mut self) -> Duration {
+        if !self.pondering {
+            return Duration::ZERO;
+        }
+        let pondered = self.elapsed();
+        self.pondering = false;
+        self.set_budgets(pondered);
+        pondered
+    }
+
+    /// Returns the soft budget, before scaling it by the search stability, none while pondering.
+    pub fn soft(&self) -> Option<Duration> {
+        self.soft
+    }
+
+    /// Returns the hard budget, none while pondering.
+    pub fn hard(&self) -> Option<Duration> {
+        self.hard
+    }
+
+    /// Records the result of the finished iteration.
+    ///
+    /// Tells whether the best move is different from the previous iteration,
+    /// and whether the score dropped below the previous one.
+    pub fn update(&mut self, best_move_changed: bool, fail_low: bool) {
+        self.stable_iterations = match best_move_changed {
+            true => 0,
+            false => self.stable_iterations + 1,
+        };
+        self.fail_low = fail_low;
+    }
+
+    /// Returns the soft budget scaled by the search stability, never above the hard one.
+    pub fn scaled_soft(&self) -> Option<Duration> {
+        let mut scale = match self.stable_iterations {
+            0 => self.params.unstable,
+            n if n >= self.params.stable_iterations => self.params.stable,
+            _ => 100,
+        };
+        if self.fail_low {
+            scale += self.params.fail_low;
+        }
+        let soft = self
+            .soft?
+            .checked_mul(scale as u32)
+            .unwrap_or(Duration::MAX)
+            / 100;
+        Some(soft.min(self.hard?))
+    }
+
+    /// Tells if the next iteration shouldn't be started.
+    pub fn should_stop(&self) -> bool {
+        !self.pondering
+            && self
+                .scaled_soft()
+                .is_some_and(|soft| self.elapsed() >= soft)
+    }
+
+    /// Tells if the search must be stopped now.
+    pub fn should_abort(&self) -> bool {
+        !self.pondering && self.hard.is_some_and(|hard| self.elapsed() >= hard)
+    }
+}
+
+/// Returns the soft and hard budgets in milliseconds, or nothing when the search has no time limit.
+fn budgets(limits: &Limits, color: Color, params: &TimeParams) -> Option<(u64, u64)> {
+    if limits.infinite {
+        return None;
+    }
+    if let Some(movetime) = limits.movetime {
+        let time = movetime.saturating_sub(params.overhead).max(1);
+        return Some((time, time));
+    }
+    let (time, increment) = limits.clock(color);
+    let available = time?.saturating_sub(params.overhead).max(1);
+    let moves = limits.movestogo.unwrap_or(params.horizon).max(1) as u64;
+    let max = percent(available, params.max_usage).max(1);
+    let soft = (available / moves)
+        .saturating_add(percent(increment, params.increment))
+        .min(max);
+    let hard = percent(soft, params.hard).min(max);
+    Some((soft.max(1), hard.max(1)))
+}
+
+/// Returns the `percent` of the `value`, without overflowing on the huge clocks.
+fn percent(value: u64, percent: u64) -> u64 {
+    (value as u128 * percent as u128 / 100).min(u64::MAX as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_limits() {
+        let limits = Limits::parse(
+            "wtime 60000 btime 30000 winc 1000 binc 0 movestogo 20 depth 12 nodes 100000 mate 3 \
+             searchmoves e2e4 d2d4 ponder foo",
+        );
+        assert_eq!(
+            limits,
+            Limits {
+                wtime: Some(60000),
+                btime: Some(30000),
+                winc: Some(1000),
+                binc: Some(0),
+                movestogo: Some(20),
+                depth: Some(12),
+                nodes: Some(100000),
+                mate: Some(3),
+                searchmoves: vec!["e2e4".to_string(), "d2d4".to_string()],
+                ponder: true,
+                ..Limits::default()
+            }
+        );
+        assert_eq!(Limits::parse(&limits.to_string()), limits);
+        assert_eq!(Limits::parse("depth x infinite").to_string(), "infinite");
+        assert_eq!(Limits::parse("movetime 500").to_string(), "movetime 500");
+    }
+
+    #[test]
+    fn time_budgets() {
+        let params = TimeParams::DEFAULT;
+        let budgets = |fields, color| budgets(&Limits::parse(fields), color, &params);
+        assert_eq!(budgets("movetime 1000", Color::White), Some((970, 970)));
+        assert_eq!(
+            budgets("wtime 60000 winc 1000", Color::White),
+            Some((2749, 10996))
+        );
+        assert_eq!(
+            budgets("wtime 60000 btime 10 movestogo 1", Color::Black),
+            Some((1, 1))
+        );
+        assert_eq!(budgets("wtime 60000", Color::Black), None);
+        assert_eq!(budgets("wtime 60000 infinite", Color::White), None);
+        assert_eq!(budgets("depth 5", Color::White), None);
+
+        // The part of the remaining time used for one move is limited.
+        let (soft, hard) = budgets("wtime 1000 winc 5000", Color::White).unwrap();
+        assert_eq!((soft, hard), (776, 776));
+    }
+
+    #[test]
+    fn huge_clock_budgets() {
+        let params = TimeParams::DEFAULT;
+        let limits = Limits {
+            wtime: Some(u64::MAX),
+            winc: Some(u64::MAX),
+            ..Limits::default()
+        };
+        let (soft, hard) = budgets(&limits, Color::White, &params).unwrap();
+        let available = u64::MAX - params.overhead;
+        let max = percent(available, params.max_usage);
+        assert_eq!(soft, available / 30 + percent(u64::MAX, params.increment));
+        assert_eq!(hard, max);
+
+        let mut manager = TimeManager::new(&limits, Color::White, params);
+        assert_eq!(manager.scaled_soft(), manager.hard());
+        manager.update(false, true);
+        assert_eq!(manager.scaled_soft(), manager.hard());
+        assert!(!manager.should_stop());
+        assert!(!manager.should_abort());
+    }
+
+    #[test]
+    fn scaled_soft_budget() {
+        let limits = Limits::parse("wtime 60000 winc 1000");
+        let mut manager = TimeManager::new(&limits, Color::White, TimeParams::DEFAULT);
+        assert_eq!(
+            manager.scaled_soft(),
+            Some(Duration::from_micros(2749 * 1500))
+        );
+        manager.update(false, false);
+        assert_eq!(manager.scaled_soft(), manager.soft());
+        for _ in 0..4 {
+            manager.update(false, false);
+        }
+        assert_eq!(
+            manager.scaled_soft(),
+            Some(Duration::from_micros(2749 * 700))
+        );
+        manager.update(false, true);
+        assert_eq!(
+            manager.scaled_soft(),
+            Some(Duration::from_micros(2749 * 1200))
+        );
+        assert!(!manager.should_abort());
+    }
+
+    #[test]
+    fn pondering_budgets() {
+        let limits = Limits::parse("wtime 60000 winc 1000 ponder");
+        let mut manager = TimeManager::new(&limits, Color::White, TimeParams::DEFAULT);
+        assert!(manager.pondering());
+        assert_eq!((manager.soft(), manager.hard()), (None, None));
+        manager.update(false, true);
+        assert!(!manager.should_stop());
+        assert!(!manager.should_abort());
+
+        std::thread::sleep(Duration::from_millis(5));
+        let pondered = manager.ponderhit();
+        assert!(pondered >= Duration::from_millis(5));
+        assert!(!manager.pondering());
+        assert_eq!(manager.soft(), Some(Duration::from_millis(2749)));
+        assert_eq!(
+            manager.hard(),
+            Some(pondered + Duration::from_millis(10996))
+        );
+        assert_eq!(manager.ponderhit(), Duration::ZERO);
+
+        // The pondering used up the whole soft budget.
+        let limits = Limits::parse("movetime 35 ponder");
+        let mut manager = TimeManager::new(&limits, Color::Black, TimeParams::DEFAULT);
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(!manager.should_stop());
+        manager.ponderhit();
+        assert!(manager.should_stop());
+    }
+
+    #[test]
+    fn move_overhead_option() {
+        let mut options = Options::new();
+        TimeParams::register_options(&mut options);
+        let mut params = TimeParams::DEFAULT;
+        options
+            .set_from_command("setoption name Move Overhead value 100")
+            .unwrap();
+        params.configure(&mut options);
+        assert_eq!(params.overhead, 100);
+        let limits = Limits::parse("movetime 1000");
+        assert_eq!(budgets(&limits, Color::White, &params), Some((900, 900)));
+    }
+
+    #[test]
+    fn nodestime_option() {
+        let mut options = Options::new();
+        TimeParams::register_options(&mut options);
+        let mut params = TimeParams::DEFAULT;
+        options
+            .set_from_command("setoption name nodestime value 10")
+            .unwrap();
+        params.configure(&mut options);
+        assert_eq!(params.nodestime, 10);
+
+        // The soft budget is 2749 ms and the hard one 10996 ms, standing for ten times the nodes.
+        let limits = Limits::parse("wtime 60000 winc 1000");
+        let mut manager = TimeManager::new(&limits, Color::White, params);
+        manager.update(false, false);
+        assert_eq!(manager.elapsed(), Duration::ZERO);
+        manager.set_nodes(27489);
+        assert_eq!(manager.elapsed(), Duration::from_millis(2748));
+        assert!(!manager.should_stop());
+        manager.set_nodes(27490);
+        assert!(manager.should_stop());
+        assert!(!manager.should_abort());
+        manager.set_nodes(109960);
+        assert!(manager.should_abort());
+    }
+}
@@ -0,0 +1,317 @@
+//! Texel tuning of the evaluation weights.
+//!
+//! The tuner takes positions labelled with the results of the games they come from,
+//! and looks for the weights minimizing the mean squared difference between the results
+//! and the evaluations mapped to the expected results with a logistic function.
+//!
+//! The weights are addressed by the names given by [EvalParams::visit_weights],
+//! and can be written to and read back from simple `name value` text files.
+//!
+//! The [Spsa] optimizer tunes the weights with games instead, which also works for the terms
+//! which can't be judged by the evaluation of single positions. It registers the tuned weights
+//! in the engine [Options](crate::options::Options), so they are set the same way as the other options.
+//!
+
+use crate::eval::{evaluate_with, EvalParams, Score, WeightIndex};
+use crate::{Color, Position};
+use std::io::{self, BufRead, Write};
+use std::thread;
+
+mod spsa;
+
+pub use spsa::{Spsa, SpsaParams};
+
+/// Prefixes of the weights which don't change the evaluation, so there's no point tuning them.
+const UNTUNED: [&str; 4] = ["lazy.", "contempt.", "hybrid.", "wdl."];
+
+/// Position labelled with the result of the game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample {
+    pub position: Position,
+    /// Result of the game from the white point of view: 1 for a win, 0.5 for a draw, 0 for a loss.
+    pub result: f64,
+}
+
+impl Sample {
+    /// Parses a line with a FEN followed by the game result.
+    ///
+    /// The result can be written as `1-0`, `0-1` or `1/2-1/2`, or as a number like `[0.5]`,
+    /// optionally quoted and followed by a semicolon. Any operations between the FEN and the result
+    /// (like `c9` in the EPD files) are skipped.
+    pub fn parse(line: &str) -> Option<Self> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (result, fen) = fields.split_last()?;
+        let result = match result.trim_matches(|c| matches!(c, '"' | ';' | '[' | ']')) {
+            "1-0" => 1.0,
+            "0-1" => 0.0,
+            "1/2-1/2" => 0.5,
+            number => number
+                .parse()
+                .ok()
+                .filter(|result| (0.0..=1.0).contains(result))?,
+        };
+        let clocks = fen
+            .iter()
+            .skip(4)
+            .take(2)
+            .take_while(|field| field.parse::<u32>().is_ok())
+            .count();
+        let position = Position::from_fen(&fen[..fen.len().min(4 + clocks)].join(" ")).ok()?;
+        Some(Self { position, result })
+    }
+}
+
+/// Reads the samples, one per line, skipping the empty lines.
+///
+/// Fails with [io::ErrorKind::InvalidData] on the first line which can't be parsed.
+pub fn read_samples(reader: impl BufRead) -> io::Result<Vec<Sample>> {
+    let mut samples = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let sample = Sample::parse(&line).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid sample on line {}: {line}", number + 1),
+            )
+        })?;
+        samples.push(sample);
+    }
+    Ok(samples)
+}
+
+/// Writes all the weights of the `params`, one `name value` pair per line.
+pub fn write_weights(params: &EvalParams, mut writer: impl Write) -> io::Result<()> {
+    for (name, value) in params.weights() {
+        writeln!(writer, "{name} {value}")?;
+    }
+    Ok(())
+}
+
+/// Reads the weights written by [write_weights] into the `params`.
+///
+/// The weights missing in the input keep their values. Empty lines and lines starting with `#` are skipped.
+/// Fails with [io::ErrorKind::InvalidData] on unknown names and invalid values.
+pub fn read_weights(params: &mut EvalParams, reader: impl BufRead) -> io::Result<()> {
+    let index = WeightIndex::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid weight on line {}: {line}", number + 1),
+            )
+        };
+        let (name, value) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+        let value = value.trim().parse().map_err(|_| invalid())?;
+        let slot = index.slot(name).ok_or_else(invalid)?;
+        params.set_weight_at(slot, value);
+    }
+    Ok(())
+}
+
+/// Maps the `score` in centipawns to the expected game result, using the scaling constant `k`.
+pub fn expected_result(score: Score, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * score as f64 / 400.0))
+}
+
+/// Texel tuner working on a fixed set of samples.
+#[derive(Clone, Debug)]
+pub struct Tuner {
+    samples: Vec<Sample>,
+    k: f64,
+}
+
+impl Tuner {
+    /// Creates the tuner with the scaling constant equal to one.
+    pub fn new(samples: Vec<Sample>) -> Self {
+        Self { samples, k: 1.0 }
+    }
+
+    /// Returns the scaling constant of the logistic function.
+    pub fn k(&self) -> f64 {
+        self.k
+    }
+
+    /// Sets the scaling constant of the logistic function.
+    pub fn set_k(&mut self, k: f64) {
+        self.k = k;
+    }
+
+    /// Returns the mean squared error of the evaluation with the `params` over all the samples.
+    ///
+    /// The samples are split between all the available threads.
+    pub fn error(&self, params: &EvalParams) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+        let chunk = self.samples.len().div_ceil(threads);
+        let total: f64 = thread::scope(|scope| {
+            let workers: Vec<_> = self
+                .samples
+                .chunks(chunk)
+                .map(|samples| scope.spawn(move || self.squared_errors(samples, params)))
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .sum()
+        });
+        total / self.samples.len() as f64
+    }
+
+    /// Sums up the squared errors of the `samples`.
+    fn squared_errors(&self, samples: &[Sample], params: &EvalParams) -> f64 {
+        samples
+            .iter()
+            .map(|sample| {
+                let score = white_score(&sample.position, params);
+                (sample.result - expected_result(score, self.k)).powi(2)
+            })
+            .sum()
+    }
+
+    /// Finds the scaling constant giving the lowest error for the `params`, and keeps it.
+    ///
+    /// This should be done once before the tuning, so the tuner changes the weights
+    /// instead of making up for the wrong scale of the whole evaluation.
+    pub fn fit_k(&mut self, params: &EvalParams) -> f64 {
+        let scores: Vec<(Score, f64)> = self
+            .samples
+            .iter()
+            .map(|sample| (white_score(&sample.position, params), sample.result))
+            .collect();
+        let error = |k| -> f64 {
+            scores
+                .iter()
+                .map(|&(score, result)| (result - expected_result(score, k)).powi(2))
+                .sum()
+        };
+
+        let (mut low, mut high) = (0.0, 4.0);
+        for _ in 0..50 {
+            let one = low + (high - low) / 3.0;
+            let two = high - (high - low) / 3.0;
+            match error(one) < error(two) {
+                true => high = two,
+                false => low = one,
+            }
+        }
+        self.k = (low + high) / 2.0;
+        self.k
+    }
+
+    /// Tunes the weights of the `params` with a local search, returning the final error.
+    ///
+    /// Each pass tries to move every weight by one in both directions, keeping the changes which lower the error.
+    /// Stops after the pass which didn't change anything, or after `max_passes`. This needs two evaluations
+    /// of all the samples per weight in each pass, so for large sets it's worth starting with few passes.
+    /// The `progress` is called after each pass with its number and the error.
+    pub fn tune(
+        &self,
+        params: &mut EvalParams,
+        max_passes: usize,
+        mut progress: impl FnMut(usize, f64),
+    ) -> f64 {
+        let slots: Vec<usize> = params
+            .weights()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, (name, _))| !UNTUNED.iter().any(|prefix| name.starts_with(prefix)))
+            .map(|(slot, _)| slot)
+            .collect();
+
+        let mut best = self.error(params);
+        for pass in 1..=max_passes {
+            let mut improved = false;
+            for &slot in &slots {
+                let value = params.weight_at(slot).unwrap();
+                for step in [1, -1] {
+                    params.set_weight_at(slot, value + step);
+                    let error = self.error(params);
+                    if error < best {
+                        best = error;
+                        improved = true;
+                        break;
+                    }
+                    params.set_weight_at(slot, value);
+                }
+            }
+            progress(pass, best);
+            if !improved {
+                break;
+            }
+        }
+        best
+    }
+}
+
+/// Evaluates the position from the white point of view, as the game results are.
+fn white_score(position: &Position, params: &EvalParams) -> Score {
+    match position.side_to_move() {
+        Color::White => evaluate_with(position, params),
+        Color::Black => -evaluate_with(position, params),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_samples() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -";
+        for (line, result) in [
+            (format!("{start} 0 1 1-0"), Some(1.0)),
+            (format!("{start} 0-1"), Some(0.0)),
+            (format!("{start} c9 \"1/2-1/2\";"), Some(0.5)),
+            (format!("{start} 3 10 [0.25]"), Some(0.25)),
+            (format!("{start} 1.5"), None),
+            (format!("{start} draw"), None),
+            ("8/8 w - - 1-0".to_string(), None),
+            (String::new(), None),
+        ] {
+            assert_eq!(
+                Sample::parse(&line).map(|sample| sample.result),
+                result,
+                "{line}"
+            );
+        }
+        let sample = Sample::parse(&format!("{start} 12 30 1-0")).unwrap();
+        assert_eq!(sample.position.halfmove_clock(), 12);
+
+        let text = format!("{start} 1-0\n\n{start} 0-1\n");
+        assert_eq!(read_samples(text.as_bytes()).unwrap().len(), 2);
+        let text = format!("{start} 1-0\n{start} ?\n");
+        let error = read_samples(text.as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn weights_round_trip() {
+        let mut params = EvalParams::default();
+        params.set_weight("pawns.doubled.mg", -42);
+        let mut text = Vec::new();
+        write_weights(&params, &mut text).unwrap();
+        let mut read = EvalParams::default();
+        read_weights(&mut read, text.as_slice()).unwrap();
+        assert_eq!(read.weight("pawns.doubled.mg"), Some(-42));
+        assert_eq!(read.weights(), params.weights());
+
+        let mut read = EvalParams::default();
+        read_weights(&mut read, "# comment\n\n pawns.doubled.eg  7 \n".as_bytes()).unwrap();
+        assert_eq!(read.weight("pawns.doubled.eg"), Some(7));
+        for text in ["nothing.here 1", "pawns.doubled.eg x", "pawns.doubled.eg"] {
+            let error = read_weights(&mut read, text.as_bytes()).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{text}");
+        }
+    }
+}
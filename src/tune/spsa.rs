@@ -0,0 +1,204 @@
+//! SPSA tuning of the weights with games.
+//!
+
+use crate::eval::{EvalParams, WeightIndex};
+use crate::options::{OptionKind, Options};
+use crate::zobrist;
+
+/// Smallest distance of the spin option limits from zero.
+const OPTION_RANGE: i64 = 100;
+
+/// Settings of the SPSA optimizer.
+///
+/// On the iteration `k` the weights are perturbed by `c / (k + 1)^gamma`
+/// and moved by `a / (k + 1 + stability)^alpha` times the estimated gradient.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpsaParams {
+    /// Size of the steps.
+    pub a: f64,
+    /// Size of the perturbations, in the units of the weights.
+    pub c: f64,
+    /// Number of iterations which make the first steps smaller.
+    pub stability: f64,
+    /// How fast the steps get smaller.
+    pub alpha: f64,
+    /// How fast the perturbations get smaller.
+    pub gamma: f64,
+}
+
+impl SpsaParams {
+    /// The default settings, with the usual decay exponents.
+    pub const DEFAULT: Self = Self {
+        a: 10.0,
+        c: 4.0,
+        stability: 100.0,
+        alpha: 0.602,
+        gamma: 0.101,
+    };
+}
+
+impl Default for SpsaParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Simultaneous perturbation stochastic approximation of the best weights.
+///
+/// Each iteration plays a match between two copies of the parameters, with all the tuned weights
+/// moved in random directions, one copy forward and one backward. The weights are then moved
+/// towards the winner, so the games estimate the gradient of the strength with just two players.
+///
+/// The games themselves are played by the caller, so the optimizer works with any way of running them.
+#[derive(Clone, Debug)]
+pub struct Spsa {
+    settings: SpsaParams,
+    names: Vec<String>,
+    slots: Vec<usize>,
+    values: Vec<f64>,
+    iteration: u64,
+    random: u64,
+}
+
+impl Spsa {
+    /// Creates the optimizer for the weights with the `names`, starting from their values in the `params`.
+    ///
+    /// Returns `None` if any of the names is not a weight of the `params`.
+    pub fn new(params: &EvalParams, names: Vec<String>, settings: SpsaParams) -> Option<Self> {
+        let index = WeightIndex::new();
+        let slots: Vec<usize> = names
+            .iter()
+            .map(|name| index.slot(name))
+            .collect::<Option<_>>()?;
+        let weights = params.weights();
+        let values = slots.iter().map(|&slot| weights[slot].1 as f64).collect();
+        Some(Self {
+            settings,
+            names,
+            slots,
+            values,
+            iteration: 0,
+            random: 0x2545_F491_4F6C_DD1D,
+        })
+    }
+
+    /// Returns the number of the iterations done so far.
+    pub fn iteration(&self) -> u64 {
+        self.iteration
+    }
+
+    /// Returns the names and the current, not rounded values of the tuned weights.
+    pub fn values(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.names
+            .iter()
+            .map(String::as_str)
+            .zip(self.values.iter().copied())
+    }
+
+    /// Registers the tuned weights as spin options named as the weights, with the current rounded values as the defaults.
+    ///
+    /// The limits are twice the magnitude of the value either way, but at least 100, so the engine and the tuner
+    /// share the same knobs, and the weights can be set through `setoption` too, see [Spsa::configure].
+    pub fn register_options(&self, options: &mut Options) {
+        for (name, value) in self.values() {
+            let default = value.round() as i64;
+            let range = (2 * default.abs()).max(OPTION_RANGE);
+            options.register(
+                name,
+                OptionKind::Spin {
+                    default,
+                    min: -range,
+                    max: range,
+                },
+            );
+        }
+    }
+
+    /// Applies the weight options changed since the last call, to both the `params` and the tuned values.
+    pub fn configure(&mut self, params: &mut EvalParams, options: &mut Options) {
+        for ((name, &slot), value) in self.names.iter().zip(&self.slots).zip(&mut self.values) {
+            if !options.take_changed(name) {
+                continue;
+            }
+            if let Some(spin) = options.spin(name) {
+                *value = spin as f64;
+                params.set_weight_at(slot, spin as i32);
+            }
+        }
+    }
+
+    /// Does one iteration, and writes the rounded weights into the `params`.
+    ///
+    /// The `play` gets the parameters moved forward and backward, and returns the result of their match
+    /// from the point of view of the first ones, from -1 for losing all the games to 1 for winning all of them.
+    pub fn step(
+        &mut self,
+        params: &mut EvalParams,
+        mut play: impl FnMut(&EvalParams, &EvalParams) -> f64,
+    ) {
+        let settings = self.settings;
+        let k = self.iteration as f64 + 1.0;
+        let a = settings.a / (k + settings.stability).powf(settings.alpha);
+        let c = settings.c / k.powf(settings.gamma);
+
+        let directions: Vec<f64> = (0..self.values.len()).map(|_| self.direction()).collect();
+        let perturbed = |sign: f64| {
+            let mut perturbed = params.clone();
+            let values = self.values.iter().zip(&directions);
+            let weights =
+                values.map(|(value, direction)| (value + sign * c * direction).round() as i32);
+            perturbed.set_weights_at(self.slots.iter().copied().zip(weights));
+            perturbed
+        };
+        let (forward, backward) = (perturbed(1.0), perturbed(-1.0));
+
+        let result = play(&forward, &backward);
+        for (value, direction) in self.values.iter_mut().zip(&directions) {
+            *value += a * result / (2.0 * c * direction);
+        }
+        let weights = self.values.iter().map(|value| value.round() as i32);
+        params.set_weights_at(self.slots.iter().copied().zip(weights));
+        self.iteration += 1;
+    }
+
+    /// Returns a random direction of the perturbation, either 1 or -1.
+    fn direction(&mut self) -> f64 {
+        let (state, value) = zobrist::next(self.random);
+        self.random = state;
+        match value >> 63 {
+            0 => 1.0,
+            _ => -1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weights_are_options() {
+        let mut params = EvalParams::default();
+        let names = ["piece_values.pawn.mg", "pawns.doubled.eg"]
+            .map(String::from)
+            .to_vec();
+        let mut spsa = Spsa::new(&params, names, SpsaParams::DEFAULT).unwrap();
+        let mut options = Options::new();
+        spsa.register_options(&mut options);
+        let pawn = params.weight("piece_values.pawn.mg").unwrap() as i64;
+        assert_eq!(options.spin("piece_values.pawn.mg"), Some(pawn));
+        assert_eq!(options.iter().count(), 2);
+
+        options
+            .set_from_command("setoption name piece_values.pawn.mg value 77")
+            .unwrap();
+        spsa.configure(&mut params, &mut options);
+        assert_eq!(params.weight("piece_values.pawn.mg"), Some(77));
+        assert_eq!(spsa.values().next(), Some(("piece_values.pawn.mg", 77.0)));
+
+        spsa.step(&mut params, |_, _| 1.0);
+        spsa.register_options(&mut options);
+        let pawn = params.weight("piece_values.pawn.mg").unwrap() as i64;
+        assert_eq!(options.spin("piece_values.pawn.mg"), Some(pawn));
+    }
+}
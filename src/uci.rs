@@ -0,0 +1,677 @@
+//! Client for external engines speaking UCI.
+//!
+//! [Engine] starts the engine process, does the handshake collecting its options, and sends
+//! the positions and searches to it. The lines written by the engine are parsed into [Message]s,
+//! with the search progress in [Info] and the result in [BestMove].
+//!
+//! Moves are passed in the UCI long algebraic notation, like `e2e4` or `e7e8q`.
+//!
+
+use crate::eval::Wdl;
+use crate::options::{OptionKind, Options};
+use std::ffi::OsStr;
+use std::fmt::Display;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::str::{FromStr, SplitWhitespace};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Time the engine has to quit, before it gets killed.
+const QUIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Time the engine has to answer `uci` with `uciok`, when starting it.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Reasons why talking to the engine failed.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum UciError {
+    /// The engine closed its output.
+    Closed,
+    /// The engine didn't write anything within the read timeout, see [Engine::set_read_timeout].
+    Timeout,
+    /// Starting the engine or talking to it failed.
+    Io(io::Error),
+}
+
+impl Display for UciError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UciError::Closed => write!(f, "engine closed its output"),
+            UciError::Timeout => write!(f, "engine didn't answer in time"),
+            UciError::Io(error) => write!(f, "can't talk to engine: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for UciError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UciError::Closed | UciError::Timeout => None,
+            UciError::Io(error) => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for UciError {
+    fn from(error: io::Error) -> Self {
+        UciError::Io(error)
+    }
+}
+
+/// Score reported by the engine, for the side to move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InfoScore {
+    Centipawns(i32),
+    /// Mate in the number of moves, negative when the side to move gets mated.
+    Mate(i32),
+}
+
+/// Search progress from an `info` line, without the fields the engine left out.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Info {
+    pub depth: Option<u32>,
+    pub seldepth: Option<u32>,
+    pub multipv: Option<u32>,
+    pub score: Option<InfoScore>,
+    /// The score is only a lower bound.
+    pub lowerbound: bool,
+    /// The score is only an upper bound.
+    pub upperbound: bool,
+    pub wdl: Option<Wdl>,
+    pub nodes: Option<u64>,
+    pub nps: Option<u64>,
+    /// Search time in milliseconds.
+    pub time: Option<u64>,
+    pub hashfull: Option<u32>,
+    pub pv: Vec<String>,
+    /// Text of the `info string` line.
+    pub string: Option<String>,
+}
+
+impl Info {
+    /// Parses the fields following `info`, skipping the unknown ones.
+    fn parse(fields: &str) -> Self {
+        let mut info = Info::default();
+        let mut tokens = fields.split_whitespace();
+        while let Some(token) = tokens.next() {
+            match token {
+                "depth" => info.depth = next_number(&mut tokens),
+                "seldepth" => info.seldepth = next_number(&mut tokens),
+                "multipv" => info.multipv = next_number(&mut tokens),
+                "nodes" => info.nodes = next_number(&mut tokens),
+                "nps" => info.nps = next_number(&mut tokens),
+                "time" => info.time = next_number(&mut tokens),
+                "hashfull" => info.hashfull = next_number(&mut tokens),
+                "score" => {
+                    info.score = match (tokens.next(), tokens.next().map(str::parse)) {
+                        (Some("cp"), Some(Ok(score))) => Some(InfoScore::Centipawns(score)),
+                        (Some("mate"), Some(Ok(moves))) => Some(InfoScore::Mate(moves)),
+                        _ => None,
+                    }
+                }
+                "lowerbound" => info.lowerbound = true,
+                "upperbound" => info.upperbound = true,
+                "wdl" => {
+                    let win = next_number(&mut tokens);
+                    let draw = next_number(&mut tokens);
+                    let loss = next_number(&mut tokens);
+                    if let (Some(win), Some(draw), Some(loss)) = (win, draw, loss) {
+                        info.wdl = Some(Wdl { win, draw, loss });
+                    }
+                }
+                // Both take the rest of the line.
+                "pv" => info.pv = tokens.by_ref().map(String::from).collect(),
+                "string" => info.string = Some(tokens.by_ref().collect::<Vec<_>>().join(" ")),
+                _ => {}
+            }
+        }
+        info
+    }
+}
+
+/// Parses the next token as a number.
+fn next_number<T: FromStr>(tokens: &mut SplitWhitespace) -> Option<T> {
+    tokens.next()?.parse().ok()
+}
+
+/// Result of the search.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BestMove {
+    /// `0000` when the engine has no move to play.
+    pub best: String,
+    /// The expected reply, to ponder on.
+    pub ponder: Option<String>,
+}
+
+/// Line written by the engine.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    /// `id name` or `id author`, with the text.
+    Id {
+        field: String,
+        value: String,
+    },
+    UciOk,
+    ReadyOk,
+    /// Option listed in the reply to `uci`.
+    Option {
+        name: String,
+        kind: OptionKind,
+    },
+    Info(Info),
+    BestMove(BestMove),
+    /// Anything else, kept as it is.
+    Unknown(String),
+}
+
+impl Message {
+    /// Parses the line written by the engine.
+    pub fn parse(line: &str) -> Self {
+        let line = line.trim();
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim_start();
+        match command {
+            "uciok" => Message::UciOk,
+            "readyok" => Message::ReadyOk,
+            "info" => Message::Info(Info::parse(rest)),
+            "id" => match rest.split_once(char::is_whitespace) {
+                Some((field, value)) => Message::Id {
+                    field: field.to_string(),
+                    value: value.trim().to_string(),
+                },
+                None => Message::Unknown(line.to_string()),
+            },
+            "bestmove" => {
+                let mut tokens = rest.split_whitespace();
+                match tokens.next() {
+                    Some(best) => Message::BestMove(BestMove {
+                        best: best.to_string(),
+                        ponder: match tokens.next() {
+                            Some("ponder") => tokens.next().map(String::from),
+                            _ => None,
+                        },
+                    }),
+                    None => Message::Unknown(line.to_string()),
+                }
+            }
+            "option" => match parse_option(rest) {
+                Some((name, kind)) => Message::Option { name, kind },
+                None => Message::Unknown(line.to_string()),
+            },
+            _ => Message::Unknown(line.to_string()),
+        }
+    }
+}
+
+/// Parses the fields of the `option` line, in the format written by [crate::options::EngineOption].
+fn parse_option(fields: &str) -> Option<(String, OptionKind)> {
+    let (name, rest) = fields.strip_prefix("name ")?.split_once(" type ")?;
+    let mut tokens = rest.split_whitespace();
+    let kind = tokens.next()?;
+    // Each value runs until the next keyword, so it may have spaces.
+    let mut values: Vec<(&str, String)> = Vec::new();
+    for token in tokens {
+        match (token, values.last_mut()) {
+            ("default" | "min" | "max" | "var", _) => values.push((token, String::new())),
+            (_, Some((_, value))) if value.is_empty() => value.push_str(token),
+            (_, Some((_, value))) => {
+                value.push(' ');
+                value.push_str(token);
+            }
+            (_, None) => return None,
+        }
+    }
+    let value = |key: &str| {
+        values
+            .iter()
+            .find(|(found, _)| *found == key)
+            .map(|(_, value)| value.as_str())
+    };
+    let kind = match kind {
+        "check" => OptionKind::Check {
+            default: value("default")?.eq_ignore_ascii_case("true"),
+        },
+        "spin" => {
+            let default = value("default")?.parse().ok()?;
+            let min = value("min")?.parse().ok()?;
+            let max = value("max")?.parse().ok()?;
+            if !(min..=max).contains(&default) {
+                return None;
+            }
+            OptionKind::Spin { default, min, max }
+        }
+        "combo" => OptionKind::Combo {
+            default: value("default")?.to_string(),
+            choices: values
+                .iter()
+                .filter(|(key, _)| *key == "var")
+                .map(|(_, choice)| choice.clone())
+                .collect(),
+        },
+        "button" => OptionKind::Button,
+        "string" => OptionKind::String {
+            default: match value("default").unwrap_or("") {
+                "<empty>" => String::new(),
+                default => default.to_string(),
+            },
+        },
+        _ => return None,
+    };
+    Some((name.trim().to_string(), kind))
+}
+
+/// Position sent with the `position` command, before the moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StartPosition<'a> {
+    /// The initial position of the game, sent as `startpos`.
+    Initial,
+    /// The FEN, sent as it is, so the castling rights and the en passant field are kept.
+    Fen(&'a str),
+}
+
+/// Formats the position as the fields of the `position` command.
+impl Display for StartPosition<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StartPosition::Initial => write!(f, "startpos"),
+            StartPosition::Fen(fen) => write!(f, "fen {}", fen.trim()),
+        }
+    }
+}
+
+/// External engine process.
+///
+/// The engine is asked to quit when dropped, and killed when it doesn't quit in time.
+///
+/// The lines written by the engine are read on a separate thread, so waiting for them
+/// can be given up after the read timeout.
+#[derive(Debug)]
+pub struct Engine {
+    process: Child,
+    input: ChildStdin,
+    /// Lines read from the engine output, ending when it's closed.
+    output: Receiver<io::Result<String>>,
+    read_timeout: Option<Duration>,
+    name: Option<String>,
+    author: Option<String>,
+    options: Options,
+}
+
+impl Engine {
+    /// Starts the engine from the `program` file and waits for `uciok`, failing if it doesn't come in 10 seconds.
+    pub fn spawn(program: impl AsRef<OsStr>) -> Result<Self, UciError> {
+        let mut process = Command::new(program)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let input = process.stdin.take().ok_or(UciError::Closed)?;
+        let output = process.stdout.take().ok_or(UciError::Closed)?;
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(output).lines() {
+                let failed = line.is_err();
+                if sender.send(line).is_err() || failed {
+                    break;
+                }
+            }
+        });
+        let mut engine = Self {
+            process,
+            input,
+            output: receiver,
+            read_timeout: Some(HANDSHAKE_TIMEOUT),
+            name: None,
+            author: None,
+            options: Options::new(),
+        };
+        engine.send("uci")?;
+        loop {
+            match engine.receive()? {
+                Message::UciOk => break,
+                Message::Id { field, value } if field == "name" => engine.name = Some(value),
+                Message::Id { field, value } if field == "author" => engine.author = Some(value),
+                Message::Option { name, kind } => engine.options.register(&name, kind),
+                _ => {}
+            }
+        }
+        engine.read_timeout = None;
+        Ok(engine)
+    }
+
+    /// Returns the name given by the engine.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the author given by the engine.
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    /// Returns the options listed by the engine, with their default values.
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    /// Writes the `command` line to the engine.
+    pub fn send(&mut self, command: &str) -> Result<(), UciError> {
+        writeln!(self.input, "{command}")?;
+        self.input.flush()?;
+        Ok(())
+    }
+
+    /// Sets how long [Engine::receive] waits for the next line, without any limit when `None`, as by default.
+    ///
+    /// This also limits waiting for the answers to `isready` and `go`, for each line separately.
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+    }
+
+    /// Returns the read timeout, see [Engine::set_read_timeout].
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.read_timeout
+    }
+
+    /// Reads the next line written by the engine, failing if none comes within the read timeout.
+    pub fn receive(&mut self) -> Result<Message, UciError> {
+        let line = match self.read_timeout {
+            None => self.output.recv().map_err(|_| UciError::Closed)?,
+            Some(timeout) => self
+                .output
+                .recv_timeout(timeout)
+                .map_err(|error| match error {
+                    RecvTimeoutError::Timeout => UciError::Timeout,
+                    RecvTimeoutError::Disconnected => UciError::Closed,
+                })?,
+        };
+        Ok(Message::parse(&line?))
+    }
+
+    /// Sets the option, leaving checking the `value` to the engine.
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), UciError> {
+        match value.is_empty() {
+            true => self.send(&format!("setoption name {name}")),
+            false => self.send(&format!("setoption name {name} value {value}")),
+        }
+    }
+
+    /// Waits until the engine has handled all the commands sent so far.
+    pub fn wait_ready(&mut self) -> Result<(), UciError> {
+        self.send("isready")?;
+        while self.receive()? != Message::ReadyOk {}
+        Ok(())
+    }
+
+    /// Tells the engine the next search is from a different game.
+    pub fn new_game(&mut self) -> Result<(), UciError> {
+        self.send("ucinewgame")?;
+        self.wait_ready()
+    }
+
+    /// Sets the position to search, reached with the `moves` from the `position`.
+    pub fn set_position(
+        &mut self,
+        position: StartPosition,
+        moves: &[&str],
+    ) -> Result<(), UciError> {
+        let mut command = format!("position {position}");
+        if !moves.is_empty() {
+            command = format!("{command} moves {}", moves.join(" "));
+        }
+        self.send(&command)
+    }
+
+    /// Searches with the `limits`, e.g. `depth 10` or `movetime 1000`, until the engine gives the best move.
+    ///
+    /// Each `info` line is passed to the `progress`.
+    pub fn go(
+        &mut self,
+        limits: &str,
+        mut progress: impl FnMut(&Info),
+    ) -> Result<BestMove, UciError> {
+        self.send(format!("go {limits}").trim_end())?;
+        loop {
+            match self.receive()? {
+                Message::Info(info) => progress(&info),
+                Message::BestMove(best) => return Ok(best),
+                _ => {}
+            }
+        }
+    }
+
+    /// Asks the engine to quit, and waits until it does, killing it when it takes over a second.
+    pub fn quit(mut self) -> Result<(), UciError> {
+        self.send("quit")?;
+        self.wait_or_kill()
+    }
+
+    /// Waits for the engine process to end, killing it when it's still running after [QUIT_TIMEOUT].
+    fn wait_or_kill(&mut self) -> Result<(), UciError> {
+        let start = Instant::now();
+        while self.process.try_wait()?.is_none() {
+            if start.elapsed() >= QUIT_TIMEOUT {
+                self.process.kill()?;
+                self.process.wait()?;
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        // The engine may have quit already.
+        let _ = self.send("quit");
+        let _ = self.wait_or_kill();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_messages() {
+        assert_eq!(Message::parse("uciok\n"), Message::UciOk);
+        assert_eq!(Message::parse("  readyok "), Message::ReadyOk);
+        assert_eq!(
+            Message::parse("id name Some Engine 1.0"),
+            Message::Id {
+                field: "name".to_string(),
+                value: "Some Engine 1.0".to_string()
+            }
+        );
+        assert_eq!(
+            Message::parse("bestmove e2e4 ponder e7e5"),
+            Message::BestMove(BestMove {
+                best: "e2e4".to_string(),
+                ponder: Some("e7e5".to_string())
+            })
+        );
+        assert_eq!(
+            Message::parse("bestmove 0000"),
+            Message::BestMove(BestMove {
+                best: "0000".to_string(),
+                ponder: None
+            })
+        );
+        for line in [
+            "bestmove",
+            "id name",
+            "option name Hash type spin",
+            "copyprotection ok",
+            "",
+        ] {
+            assert_eq!(Message::parse(line), Message::Unknown(line.to_string()));
+        }
+    }
+
+    #[test]
+    fn parse_info() {
+        let Message::Info(info) = Message::parse(
+            "info depth 12 seldepth 20 multipv 1 score cp -35 lowerbound wdl 100 800 100 \
+             nodes 123456 nps 654321 time 188 hashfull 12 foo pv e2e4 e7e5 g1f3",
+        ) else {
+            panic!("not an info line");
+        };
+        assert_eq!(info.depth, Some(12));
+        assert_eq!(info.seldepth, Some(20));
+        assert_eq!(info.multipv, Some(1));
+        assert_eq!(info.score, Some(InfoScore::Centipawns(-35)));
+        assert!(info.lowerbound && !info.upperbound);
+        assert_eq!(
+            info.wdl,
+            Some(Wdl {
+                win: 100,
+                draw: 800,
+                loss: 100
+            })
+        );
+        assert_eq!(info.nodes, Some(123456));
+        assert_eq!(info.nps, Some(654321));
+        assert_eq!(info.time, Some(188));
+        assert_eq!(info.hashfull, Some(12));
+        assert_eq!(info.pv, ["e2e4", "e7e5", "g1f3"]);
+        assert_eq!(
+            Message::parse("info score mate -3 string mate  soon"),
+            Message::Info(Info {
+                score: Some(InfoScore::Mate(-3)),
+                string: Some("mate soon".to_string()),
+                ..Info::default()
+            })
+        );
+    }
+
+    #[test]
+    fn parse_options() {
+        let option = |line| match Message::parse(line) {
+            Message::Option { name, kind } => Some((name, kind)),
+            _ => None,
+        };
+        assert_eq!(
+            option("option name Hash type spin default 16 min 1 max 1024"),
+            Some((
+                "Hash".to_string(),
+                OptionKind::Spin {
+                    default: 16,
+                    min: 1,
+                    max: 1024
+                }
+            ))
+        );
+        assert_eq!(
+            option("option name Hash type spin default 0 min 1 max 1024"),
+            None
+        );
+        assert_eq!(
+            option("option name Style type combo default Very Solid var Very Solid var Risky"),
+            Some((
+                "Style".to_string(),
+                OptionKind::Combo {
+                    default: "Very Solid".to_string(),
+                    choices: vec!["Very Solid".to_string(), "Risky".to_string()]
+                }
+            ))
+        );
+        assert_eq!(
+            option("option name EvalFile type string default <empty>"),
+            Some((
+                "EvalFile".to_string(),
+                OptionKind::String {
+                    default: String::new()
+                }
+            ))
+        );
+        assert_eq!(
+            option("option name Clear Hash type button"),
+            Some(("Clear Hash".to_string(), OptionKind::Button))
+        );
+        assert_eq!(
+            option("option name Ponder type check default true"),
+            Some(("Ponder".to_string(), OptionKind::Check { default: true }))
+        );
+    }
+
+    #[test]
+    fn start_position_keeps_the_fen() {
+        let fen = "r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1";
+        assert_eq!(StartPosition::Fen(fen).to_string(), format!("fen {fen}"));
+        assert_eq!(StartPosition::Initial.to_string(), "startpos");
+    }
+
+    /// Writes the shell script with the `commands` pattern cases, answering like an engine.
+    #[cfg(unix)]
+    fn fake_engine(name: &str, commands: &str) -> std::path::PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = std::env::temp_dir().join(format!("sl_c3_{name}_{}.sh", std::process::id()));
+        let source = format!(
+            "#!/bin/sh\nwhile read line; do\n  case \"$line\" in\n{commands}  esac\ndone\n"
+        );
+        std::fs::write(&script, source).unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        script
+    }
+
+    /// Talks to a shell script answering like an engine, which ignores `quit` to be killed.
+    #[cfg(unix)]
+    #[test]
+    fn engine() {
+        let script = fake_engine(
+            "engine",
+            "    uci) echo 'id name Fake 1.0'; echo 'option name Hash type spin default 16 min 1 max 64'; echo uciok;;\n\
+                 isready) echo readyok;;\n\
+                 position*) echo \"info string $line\";;\n\
+                 go*) echo 'info depth 1 score cp 5 pv e2e4'; echo 'bestmove e2e4';;\n",
+        );
+
+        let mut engine = Engine::spawn(&script).unwrap();
+        assert_eq!(engine.name(), Some("Fake 1.0"));
+        assert_eq!(engine.options().spin("hash"), Some(16));
+        engine.new_game().unwrap();
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        engine
+            .set_position(StartPosition::Fen(fen), &["e1g1"])
+            .unwrap();
+        let Message::Info(info) = engine.receive().unwrap() else {
+            panic!("no position echo");
+        };
+        assert_eq!(info.string, Some(format!("position fen {fen} moves e1g1")));
+        let mut depths = Vec::new();
+        let best = engine
+            .go("depth 1", |info| depths.push(info.depth))
+            .unwrap();
+        assert_eq!(best.best, "e2e4");
+        assert_eq!(depths, [Some(1)]);
+
+        let start = Instant::now();
+        engine.quit().unwrap();
+        assert!(start.elapsed() < QUIT_TIMEOUT * 5);
+        std::fs::remove_file(&script).unwrap();
+        assert!(Engine::spawn("/nonexistent/engine").is_err());
+    }
+
+    /// Gives up waiting for a script which never answers `isready` nor `go`.
+    #[cfg(unix)]
+    #[test]
+    fn read_timeout() {
+        let script = fake_engine("hung_engine", "    uci) echo uciok;;\n");
+        let mut engine = Engine::spawn(&script).unwrap();
+        assert_eq!(engine.read_timeout(), None);
+        engine.set_read_timeout(Some(Duration::from_millis(50)));
+        let start = Instant::now();
+        assert!(matches!(engine.wait_ready(), Err(UciError::Timeout)));
+        assert!(matches!(
+            engine.go("depth 1", |_| {}),
+            Err(UciError::Timeout)
+        ));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        engine.quit().unwrap();
+        std::fs::remove_file(&script).unwrap();
+    }
+}
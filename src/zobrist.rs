@@ -0,0 +1,56 @@
+//! Zobrist keys for hashing the positions.
+//!
+//! The keys are pseudo-random numbers generated at compile time, so they are the same on every run.
+//!
+
+use crate::{Color, Field, Piece};
+
+/// Seed of the random numbers generator.
+const SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Advances the xorshift64* generator, returning its new state and the generated number.
+pub(crate) const fn next(state: u64) -> (u64, u64) {
+    let mut x = state;
+    x ^= x >> 12;
+    x ^= x << 25;
+    x ^= x >> 27;
+    (x, x.wrapping_mul(0x2545_F491_4F6C_DD1D))
+}
+
+/// Generates keys for all the pieces on all the fields.
+const fn piece_keys() -> [[[u64; 64]; 6]; 2] {
+    let mut keys = [[[0; 64]; 6]; 2];
+    let mut state = SEED;
+    let mut color = 0;
+    while color < 2 {
+        let mut piece = 0;
+        while piece < 6 {
+            let mut field = 0;
+            while field < 64 {
+                let (new_state, key) = next(state);
+                state = new_state;
+                keys[color][piece][field] = key;
+                field += 1;
+            }
+            piece += 1;
+        }
+        color += 1;
+    }
+    keys
+}
+
+/// Keys for each piece of each color standing on each field.
+static PIECE_KEYS: [[[u64; 64]; 6]; 2] = piece_keys();
+
+/// Key for the black side to move, generated from the last of the piece keys.
+const SIDE_KEY: u64 = next(PIECE_KEYS[1][5][63]).1;
+
+/// Returns the key for the `piece` of the `color` standing on the `field`.
+pub fn piece_key(color: Color, piece: Piece, field: Field) -> u64 {
+    PIECE_KEYS[color as usize][piece as usize][field as usize]
+}
+
+/// Returns the key toggled when the side to move changes.
+pub fn side_key() -> u64 {
+    SIDE_KEY
+}